@@ -1,22 +1,189 @@
 use std::{
+    collections::{HashMap, HashSet, VecDeque, hash_map::Entry},
+    ops::Range,
+    path::{Path, PathBuf},
     process::Command,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use ratatui::{
-    prelude::Color,
-    widgets::TableState,
-};
+use ratatui::{layout::Rect, widgets::TableState};
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessStatus, System, Users};
 
-use crate::system_data::{ProcessInfo, get_system_processes};
+use crate::config::{Config, LayoutPreset, NameTruncation, Preferences, QuitConfirm};
+use crate::snapshot::{Snapshot, SnapshotDiff};
+use crate::system_data::{
+    ENVIRONMENT_SUPPORTED, FD_COUNT_SUPPORTED, NET_SUPPORTED, NetCounters, ProcessInfo,
+    SystemSummary, THREADS_SUPPORTED, ThreadInfo, TreeRow, build_tree, byte_rate, current_user,
+    export_snapshot, export_snapshot_csv, get_environment, get_net_counters, get_system_processes,
+    get_system_summary, get_threads, is_blocked_on_io, is_kernel_thread, is_secret_variable,
+    thread_cpu_usage,
+};
+#[cfg(unix)]
+use crate::system_data::{get_priority, resume_process, set_priority, suspend_process};
+use crate::theme::{Theme, colors_disabled};
+use crate::utils::shell_quote;
 
-pub const REFRESH_RATE: u64 = 2;
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const REFRESH_INTERVAL_STEP: Duration = Duration::from_millis(500);
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long a process gets to exit after SIGTERM before the app offers SIGKILL
+const TERM_GRACE: Duration = Duration::from_secs(3);
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SortColumn {
     Pid,
     Name,
     Memory,
+    Cpu,
+    Fds,
+    Threads,
+    /// Bytes read plus written since the previous refresh
+    DiskIo,
+    Uptime,
+}
+
+impl SortColumn {
+    /// How the sort column is named in the log
+    pub fn name(self) -> &'static str {
+        match self {
+            SortColumn::Pid => "pid",
+            SortColumn::Name => "name",
+            SortColumn::Memory => "memory",
+            SortColumn::Cpu => "cpu",
+            SortColumn::Fds => "fds",
+            SortColumn::Threads => "threads",
+            SortColumn::DiskIo => "disk_io",
+            SortColumn::Uptime => "uptime",
+        }
+    }
+
+    /// The column that is hidden by default and only collected while shown, if any
+    pub fn optional_column(self) -> Option<Column> {
+        match self {
+            SortColumn::Fds => Some(Column::Fds),
+            SortColumn::Threads => Some(Column::Threads),
+            SortColumn::DiskIo => Some(Column::DiskRead),
+            SortColumn::Uptime => Some(Column::Uptime),
+            _ => None,
+        }
+    }
+}
+
+/// Columns the process table can show, `ALL` is the display order
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Pid,
+    /// One glyph for the process state
+    Status,
+    Name,
+    Cpu,
+    Memory,
+    Fds,
+    Threads,
+    /// Disk read rate over the last refresh interval
+    DiskRead,
+    /// Disk write rate over the last refresh interval
+    DiskWrite,
+    /// Time since the process started
+    Uptime,
+}
+
+impl Column {
+    pub const ALL: [Column; 10] = [
+        Column::Pid,
+        Column::Status,
+        Column::Name,
+        Column::Cpu,
+        Column::Memory,
+        Column::Fds,
+        Column::Threads,
+        Column::DiskRead,
+        Column::DiskWrite,
+        Column::Uptime,
+    ];
+
+    /// The name used for the column in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::Status => "status",
+            Column::Name => "name",
+            Column::Cpu => "cpu",
+            Column::Memory => "memory",
+            Column::Fds => "fds",
+            Column::Threads => "threads",
+            Column::DiskRead => "disk_read",
+            Column::DiskWrite => "disk_write",
+            Column::Uptime => "uptime",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|column| column.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Header text split around the highlighted sort key, which is empty if the column can't be sorted
+    pub fn header(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Column::Pid => ("", "P", "ID"),
+            Column::Status => ("St", "", ""),
+            Column::Name => ("", "N", "ame"),
+            Column::Cpu => ("CP", "U", " %"),
+            Column::Memory => ("", "M", "emory"),
+            Column::Fds => ("", "F", "Ds"),
+            Column::Threads => ("T", "h", "reads"),
+            Column::DiskRead => ("D", "i", "sk read/s"),
+            Column::DiskWrite => ("Disk write/s", "", ""),
+            Column::Uptime => ("Uptime", "", ""),
+        }
+    }
+
+    pub fn sort_column(self) -> Option<SortColumn> {
+        match self {
+            Column::Pid => Some(SortColumn::Pid),
+            Column::Status => None,
+            Column::Name => Some(SortColumn::Name),
+            Column::Cpu => Some(SortColumn::Cpu),
+            Column::Memory => Some(SortColumn::Memory),
+            Column::Fds => Some(SortColumn::Fds),
+            Column::Threads => Some(SortColumn::Threads),
+            // Both disk columns sort by total I/O, so they share the indicator
+            Column::DiskRead | Column::DiskWrite => Some(SortColumn::DiskIo),
+            Column::Uptime => Some(SortColumn::Uptime),
+        }
+    }
+
+    /// Share of the table width, relative to the other visible columns
+    pub fn weight(self) -> u16 {
+        match self {
+            Column::Pid => 15,
+            Column::Status => 5,
+            Column::Name => 55,
+            Column::Cpu => 15,
+            Column::Memory => 30,
+            Column::Fds => 15,
+            Column::Threads => 15,
+            Column::DiskRead => 20,
+            Column::DiskWrite => 20,
+            Column::Uptime => 20,
+        }
+    }
+
+    pub fn is_supported(self) -> bool {
+        match self {
+            Column::Fds => FD_COUNT_SUPPORTED,
+            Column::Threads => THREADS_SUPPORTED,
+            _ => true,
+        }
+    }
 }
 
 #[derive(PartialEq, Copy, Clone)]
@@ -24,36 +191,594 @@ pub enum InputMode {
     Normal,
     Search,
     ConfirmKill,
+    ConfirmQuit,
+    SnapshotGone,
+    #[cfg(unix)]
+    Renice,
+    /// Typing a `:` command
+    Command,
+    /// The keybinding overlay, any key closes it
+    Help,
+    /// The `c` checklist of columns
+    ColumnMenu,
+    /// The PID to jump to is hidden by a filter, asking whether to clear them
+    ConfirmJump,
+    /// The `l` log of messages and actions
+    Log,
+    /// Typing an exact refresh interval in milliseconds
+    SetInterval,
+}
+
+/// Processes `kill <name>` won't touch without `--force`, losing them takes the system down
+const PROTECTED_NAMES: [&str; 6] = ["init", "systemd", "launchd", "kthreadd", "sshd", "login"];
+
+/// What a `:` command line asks for
+#[derive(Debug, PartialEq)]
+pub enum AppCommand {
+    /// `kill <name> [--force]`, every process with exactly that name
+    Kill { name: String, force: bool },
+    /// `user [<name>]`, only show processes owned by `name`, or everyone's without one
+    User { name: Option<String> },
+    /// A bare number, select that PID
+    Jump(Pid),
+}
+
+impl AppCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("kill") => {
+                let mut name = None;
+                let mut force = false;
+                for word in words {
+                    match word {
+                        "--force" => force = true,
+                        _ if name.is_none() => name = Some(word.to_string()),
+                        _ => {
+                            return Err(format!(
+                                "Unexpected '{word}', usage: kill <name> [--force]"
+                            ));
+                        }
+                    }
+                }
+                let name = name.ok_or("Usage: kill <name> [--force]")?;
+                Ok(AppCommand::Kill { name, force })
+            }
+            Some("user") => {
+                let name = words.next().map(str::to_string);
+                if let Some(extra) = words.next() {
+                    return Err(format!("Unexpected '{extra}', usage: user [<name>]"));
+                }
+                Ok(AppCommand::User { name })
+            }
+            Some(word) if word.starts_with(|c: char| c.is_ascii_digit()) => {
+                let pid = word
+                    .parse()
+                    .map_err(|_| format!("'{word}' is not a PID, those are plain numbers"))?;
+                if let Some(extra) = words.next() {
+                    return Err(format!("Unexpected '{extra}', a PID stands alone"));
+                }
+                Ok(AppCommand::Jump(pid))
+            }
+            Some(other) => Err(format!(
+                "Unknown command '{other}', try kill <name>, user <name> or a PID"
+            )),
+            None => Err("Empty command".to_string()),
+        }
+    }
+}
+
+/// How much room the details panel gets, `D` cycles through them
+#[derive(PartialEq, Copy, Clone)]
+pub enum DetailsLayout {
+    /// The preset's `details_height`
+    Standard,
+    /// One summary line
+    Compact,
+    /// Half the screen, grouped into sections
+    Expanded,
+}
+
+impl DetailsLayout {
+    pub fn next(self) -> Self {
+        match self {
+            DetailsLayout::Standard => DetailsLayout::Compact,
+            DetailsLayout::Compact => DetailsLayout::Expanded,
+            DetailsLayout::Expanded => DetailsLayout::Standard,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DetailsLayout::Standard => "standard",
+            DetailsLayout::Compact => "compact",
+            DetailsLayout::Expanded => "expanded",
+        }
+    }
+}
+
+/// Restricts the table to processes in particular states
+#[derive(PartialEq, Copy, Clone)]
+pub enum StatusFilter {
+    All,
+    /// Anything that isn't sleeping or idle
+    Active,
+    /// Stuck in uninterruptible sleep, usually waiting on storage
+    Blocked,
+}
+
+impl StatusFilter {
+    pub fn matches(self, process: &ProcessInfo) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Active => {
+                !matches!(process.status, ProcessStatus::Sleep | ProcessStatus::Idle)
+            }
+            StatusFilter::Blocked => is_blocked_on_io(process.status),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            StatusFilter::All => StatusFilter::Active,
+            StatusFilter::Active => StatusFilter::Blocked,
+            StatusFilter::Blocked => StatusFilter::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusFilter::All => "all",
+            StatusFilter::Active => "not sleeping",
+            StatusFilter::Blocked => "blocked on I/O",
+        }
+    }
+}
+
+/// How a message should be styled, the theme decides the actual colors
+#[derive(PartialEq, Copy, Clone)]
+pub enum Severity {
+    Success,
+    Error,
+    /// Only ever logged, for actions that don't show a message
+    Info,
+}
+
+/// Entries the log keeps before dropping the oldest
+const LOG_LEN: usize = 500;
+
+/// One line of the `l` log
+pub struct LogEntry {
+    /// Seconds since the epoch
+    pub at: u64,
+    pub text: String,
+    pub severity: Severity,
+}
+
+/// When a process was first seen in its current status
+pub struct StatusSince {
+    pub status: ProcessStatus,
+    pub start_time: u64,
+    pub since: Instant,
+}
+
+/// What the kill confirmation sends to the process
+#[derive(PartialEq, Copy, Clone)]
+pub enum KillSignal {
+    Term,
+    Kill,
+    Stop,
+    Cont,
+}
+
+impl KillSignal {
+    pub const ALL: [KillSignal; 4] = [
+        KillSignal::Term,
+        KillSignal::Kill,
+        KillSignal::Stop,
+        KillSignal::Cont,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KillSignal::Term => "SIGTERM",
+            KillSignal::Kill => "SIGKILL",
+            KillSignal::Stop => "SIGSTOP",
+            KillSignal::Cont => "SIGCONT",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            KillSignal::Term => "ask it to exit",
+            KillSignal::Kill => "force it to exit",
+            KillSignal::Stop => "pause it",
+            KillSignal::Cont => "resume it",
+        }
+    }
+
+    /// For summing up a bulk kill, like "Killed 3"
+    pub fn past_tense(self) -> &'static str {
+        match self {
+            KillSignal::Term | KillSignal::Kill => "Killed",
+            KillSignal::Stop => "Stopped",
+            KillSignal::Cont => "Resumed",
+        }
+    }
+
+    /// Windows can only terminate a process, not pause or resume it
+    pub fn is_supported(self) -> bool {
+        !cfg!(target_os = "windows") || matches!(self, KillSignal::Term | KillSignal::Kill)
+    }
+}
+
+/// Samples kept per process for the details sparklines
+pub const HISTORY_LEN: usize = 60;
+
+/// Recent CPU and memory samples of a process, oldest first
+#[derive(Default)]
+pub struct UsageHistory {
+    /// Tenths of a percent, sparklines only take integers
+    pub cpu: VecDeque<u64>,
+    pub memory_mb: VecDeque<u64>,
+}
+
+impl UsageHistory {
+    fn push(&mut self, process: &ProcessInfo) {
+        if self.cpu.len() == HISTORY_LEN {
+            self.cpu.pop_front();
+            self.memory_mb.pop_front();
+        }
+        self.cpu
+            .push_back((process.cpu_usage * 10.0).round() as u64);
+        self.memory_mb.push_back(process.memory_mb.round() as u64);
+    }
+}
+
+/// Draws the debug overlay averages its frame times over
+const FRAME_SAMPLES: usize = 30;
+
+/// Timings for the `F12` debug overlay
+#[derive(Default)]
+pub struct DebugStats {
+    /// How long the latest draws took, oldest first
+    frame_times: VecDeque<Duration>,
+    /// When each draw of the last second finished
+    frames: VecDeque<Instant>,
+    /// Set when an event arrives, cleared by the draw that shows its effect
+    event_received: Option<Instant>,
+    /// From an event arriving to the end of the draw after it
+    pub event_latency: Duration,
+    /// How long reading and sorting the process list took last time
+    pub refresh_took: Duration,
+}
+
+impl DebugStats {
+    /// Call after every draw, with when it started
+    pub fn record_frame(&mut self, started: Instant) {
+        let now = Instant::now();
+        if self.frame_times.len() == FRAME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(now - started);
+
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|&frame| now - frame > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+
+        if let Some(received) = self.event_received.take() {
+            self.event_latency = now - received;
+        }
+    }
+
+    pub fn record_event(&mut self) {
+        self.event_received = Some(Instant::now());
+    }
+
+    pub fn last_frame(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    pub fn average_frame(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    /// Draws in the last second. The app only redraws when something changes, so this is
+    /// how busy the loop is rather than a frame rate limit.
+    pub fn frames_per_second(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+/// The process the renice popup was opened for, with the nice value typed so far
+#[cfg(unix)]
+pub struct ReniceTarget {
+    pub pid: Pid,
+    pub name: String,
+    pub input: String,
+}
+
+/// The processes a kill confirmation was opened for
+pub struct KillTarget {
+    pub processes: Vec<(Pid, String)>,
+    pub signal: KillSignal,
+    /// When the confirmation opened, for the auto-cancel
+    pub opened: Instant,
+    /// Opened by the app because these ignored SIGTERM, rather than by the user
+    pub escalation: bool,
+}
+
+impl KillTarget {
+    fn new(processes: Vec<(Pid, String)>) -> Self {
+        Self {
+            processes,
+            signal: KillSignal::Term,
+            opened: Instant::now(),
+            escalation: false,
+        }
+    }
+}
+
+/// A process sent SIGTERM that hasn't been seen exiting yet
+struct PendingKill {
+    name: String,
+    /// Tells the process apart from a new one that got the same PID
+    start_time: u64,
+    deadline: Instant,
 }
 
 pub struct App {
+    pub system: System,
     pub processes: Vec<ProcessInfo>,
+    pub summary: SystemSummary,
     pub table_state: TableState,
     pub last_refresh: Instant,
+    /// Time covered by the disk I/O byte counts, the denominator for the rate columns
+    pub disk_interval: Duration,
+    disk_sampled: Instant,
+    /// How often process data is re-queried, the screen itself redraws much more often
+    pub refresh_interval: Duration,
     pub sort_column: SortColumn,
     pub sort_ascending: bool,
     pub input_mode: InputMode,
     pub search_query: String,
+    /// Match the query as a subsequence of the name and rank by how well it matches
+    pub fuzzy_search: bool,
+    /// Set while the query is a `/pattern/`
+    pub search_regex: SearchRegex,
     pub filtered_processes: Vec<usize>, // Indices to processes
-    pub message: Option<(String, Color)>,
+    pub message: Option<(String, Severity)>,
+    /// Every message and notable action this session, oldest first
+    pub log: VecDeque<LogEntry>,
+    /// Entries scrolled up from the newest in the log popup
+    pub log_scroll: usize,
+    /// Entries the log popup showed last time, one page for PgUp/PgDn
+    pub log_rows: usize,
     pub message_time: Option<Instant>,
+    pub scrolloff: usize,
+    /// Rows per page, 0 when the table scrolls instead of paging
+    pub page_size: usize,
+    /// The page holding the selection, as of the last draw
+    pub current_page: usize,
+    /// Table rows that fit on screen as of the last draw
+    pub visible_rows: usize,
+    /// Where the table was last drawn, to map mouse clicks onto rows
+    pub table_area: Rect,
+    /// Screen columns of each sortable header cell as of the last draw
+    pub header_cells: Vec<(Range<u16>, SortColumn)>,
+    /// Row and time of the last click, two quick clicks on one row open the kill confirmation
+    pub last_click: Option<(usize, Instant)>,
+    pub columns: Vec<Column>,
+    pub theme: Theme,
+    pub status_history: HashMap<Pid, StatusSince>,
+    /// Only processes that have been selected get one
+    pub usage_history: HashMap<Pid, UsageHistory>,
+    pub custom_command: Option<String>,
+    pub kill_target: Option<KillTarget>,
+    /// Processes marked with space for a bulk kill
+    pub marked: HashSet<Pid>,
+    /// Processes kept at the top of the table whatever the sort, until they exit
+    pub pinned: HashSet<Pid>,
+    /// Processes sent SIGTERM, watched until they exit or their grace period runs out
+    pending_kills: HashMap<Pid, PendingKill>,
+    /// The `:` command being typed
+    pub command_input: String,
+    /// Milliseconds typed into the refresh interval popup so far
+    pub interval_input: String,
+    /// Why the last Enter in the refresh interval popup was refused
+    pub interval_error: Option<String>,
+    /// The PID waiting on `ConfirmJump`
+    pub jump_target: Option<Pid>,
+    #[cfg(unix)]
+    pub renice_target: Option<ReniceTarget>,
+    pub confirm_quit: QuitConfirm,
+    /// `--dry-run`: kill, renice and freeze only say what they would have done
+    pub dry_run: bool,
+    /// How long the kill confirmation waits before cancelling itself, `None` waits forever
+    pub kill_timeout: Option<Duration>,
+    pub show_threads: bool,
+    pub threads: Option<Vec<ThreadInfo>>, // `None` when they couldn't be read
+    pub threads_pid: Option<Pid>,
+    pub threads_sampled: Instant,
+    pub show_environment: bool,
+    /// `(name, value)` pairs of `environment_pid`, `None` when they couldn't be read
+    pub environment: Option<Vec<(String, String)>>,
+    environment_pid: Option<Pid>,
+    /// Mask the values of variables that look like secrets
+    redact_environment: bool,
+    /// Where names too long for the name column are cut
+    pub name_truncation: NameTruncation,
+    /// Received and transmitted bytes per second for `net_pid`, `None` until two samples exist
+    pub net_rates: Option<(u64, u64)>,
+    net_pid: Option<Pid>,
+    net_counters: Option<NetCounters>,
+    net_sampled: Instant,
+    pub snapshot: Option<Snapshot>,
+    pub snapshot_diff: SnapshotDiff,
+    pub status_filter: StatusFilter,
+    /// Hide processes using less than this many MB
+    pub min_memory_mb: Option<f64>,
+    memory_thresholds_mb: Vec<f64>,
+    pub hide_kernel_threads: bool,
+    /// How many kernel threads the last `apply_filters` left out
+    pub hidden_kernel_threads: usize,
+    /// Only processes of this user are shown, plus any whose owner is unknown
+    pub user_filter: Option<String>,
+    /// Who is running this program, for the `U` shortcut
+    pub current_user: Option<String>,
+    pub users: Users,
+    pub details_height: u16, // 0 hides the details panel
+    pub details_layout: DetailsLayout,
+    /// Lines scrolled in the details text, reset whenever another process is selected
+    pub details_scroll: u16,
+    pub details_pid: Option<Pid>,
+    /// Rows of details text that fit on screen as of the last draw
+    pub details_rows: u16,
+    pub presets: Vec<LayoutPreset>,
+    pub preset_index: usize,
+    /// Set once columns are picked in the column menu, so they get saved over the preset's
+    custom_columns: bool,
+    /// Highlighted row of the column menu, an index into `Column::ALL`
+    pub column_menu_index: usize,
+    pub tree_view: bool,
+    pub show_debug: bool,
+    pub debug: DebugStats,
+    /// Show a gauge per core in the summary bar instead of one overall figure
+    pub per_core_cpu: bool,
+    /// Parallel to `filtered_processes` while the tree view is on
+    pub tree_rows: Vec<TreeRow>,
+    pub collapsed: HashSet<Pid>,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(config: &Config, preferences: Preferences) -> Self {
+        let mut system = System::new_all();
+        let users = Users::new_with_refreshed_list();
+        let current_user = current_user(&system, &users);
+        let mut processes = get_system_processes(&mut system, &users, false);
+        // The first sample counts everything since each process started, not one interval
+        for process in &mut processes {
+            process.disk_read_bytes = 0;
+            process.disk_written_bytes = 0;
+        }
+        let summary = get_system_summary(&system);
         let mut app = Self {
-            processes: get_system_processes(),
+            system,
+            processes,
+            summary,
             table_state: TableState::default(),
             last_refresh: Instant::now(),
-            sort_column: SortColumn::Memory,
-            sort_ascending: false,
+            disk_interval: DEFAULT_REFRESH_INTERVAL,
+            disk_sampled: Instant::now(),
+            refresh_interval: Duration::from_millis(preferences.refresh_interval_ms)
+                .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL),
+            sort_column: preferences.sort_column,
+            sort_ascending: preferences.sort_ascending,
             input_mode: InputMode::Normal,
             search_query: String::new(),
+            fuzzy_search: false,
+            search_regex: SearchRegex::default(),
             filtered_processes: Vec::new(),
             message: None,
+            log: VecDeque::new(),
+            log_scroll: 0,
+            log_rows: 1,
             message_time: None,
+            scrolloff: config.scrolloff,
+            page_size: config.page_size,
+            current_page: 0,
+            visible_rows: 0,
+            table_area: Rect::default(),
+            header_cells: Vec::new(),
+            last_click: None,
+            columns: Vec::new(),
+            theme: Theme::detect(),
+            status_history: HashMap::new(),
+            usage_history: HashMap::new(),
+            custom_command: config.command.clone(),
+            kill_target: None,
+            marked: HashSet::new(),
+            pinned: HashSet::new(),
+            pending_kills: HashMap::new(),
+            command_input: String::new(),
+            interval_input: String::new(),
+            interval_error: None,
+            jump_target: None,
+            #[cfg(unix)]
+            renice_target: None,
+            confirm_quit: config.confirm_quit,
+            dry_run: false,
+            kill_timeout: (config.kill_confirm_timeout_secs > 0)
+                .then(|| Duration::from_secs(config.kill_confirm_timeout_secs)),
+            show_threads: false,
+            threads: None,
+            threads_pid: None,
+            threads_sampled: Instant::now(),
+            show_environment: false,
+            environment: None,
+            environment_pid: None,
+            redact_environment: config.redact_environment,
+            name_truncation: config.name_truncation,
+            net_rates: None,
+            net_pid: None,
+            net_counters: None,
+            net_sampled: Instant::now(),
+            snapshot: None,
+            snapshot_diff: SnapshotDiff::default(),
+            status_filter: StatusFilter::All,
+            min_memory_mb: None,
+            memory_thresholds_mb: config.memory_thresholds_mb.clone(),
+            hide_kernel_threads: config.hide_kernel_threads,
+            hidden_kernel_threads: 0,
+            user_filter: None,
+            current_user,
+            users,
+            details_height: 0,
+            details_layout: DetailsLayout::Standard,
+            details_scroll: 0,
+            details_pid: None,
+            details_rows: 0,
+            presets: config.presets.clone(),
+            preset_index: 0,
+            custom_columns: false,
+            column_menu_index: 0,
+            tree_view: false,
+            show_debug: false,
+            debug: DebugStats::default(),
+            per_core_cpu: false,
+            tree_rows: Vec::new(),
+            collapsed: HashSet::new(),
         };
 
+        let preset = config
+            .preset
+            .as_ref()
+            .and_then(|name| app.presets.iter().position(|p| &p.name == name))
+            .unwrap_or(0);
+        app.apply_preset(preset);
+        if let Some(columns) = preferences.columns {
+            for column in Column::ALL {
+                app.set_column_visible(column, columns.contains(&column));
+            }
+            app.custom_columns = true;
+        }
+        if let Some(column) = app.sort_column.optional_column() {
+            if column.is_supported() {
+                app.set_column_visible(column, true);
+            } else {
+                app.sort_column = SortColumn::Memory;
+            }
+        }
+
+        app.track_status_changes();
         app.sort_processes();
         app.apply_filters();
         app.table_state.select(Some(0));
@@ -63,12 +788,10 @@ impl App {
     pub fn sort_processes(&mut self) {
         match self.sort_column {
             SortColumn::Pid => self.processes.sort_by(|a, b| {
-                let a_num = a.pid.parse::<u32>().unwrap_or(0);
-                let b_num = b.pid.parse::<u32>().unwrap_or(0);
                 if self.sort_ascending {
-                    a_num.cmp(&b_num)
+                    a.pid.cmp(&b.pid)
                 } else {
-                    b_num.cmp(&a_num)
+                    b.pid.cmp(&a.pid)
                 }
             }),
             SortColumn::Name => self.processes.sort_by(|a, b| {
@@ -89,10 +812,61 @@ impl App {
                         .unwrap_or(std::cmp::Ordering::Equal)
                 }
             }),
+            SortColumn::Cpu => self.processes.sort_by(|a, b| {
+                if self.sort_ascending {
+                    a.cpu_usage
+                        .partial_cmp(&b.cpu_usage)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                } else {
+                    b.cpu_usage
+                        .partial_cmp(&a.cpu_usage)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                }
+            }),
+            // Unknown counts (None) sort below every real count
+            SortColumn::Fds => self.processes.sort_by(|a, b| {
+                if self.sort_ascending {
+                    a.fd_count.cmp(&b.fd_count)
+                } else {
+                    b.fd_count.cmp(&a.fd_count)
+                }
+            }),
+            SortColumn::Threads => self.processes.sort_by(|a, b| {
+                if self.sort_ascending {
+                    a.threads.cmp(&b.threads)
+                } else {
+                    b.threads.cmp(&a.threads)
+                }
+            }),
+            // Newer processes have run for less time, so ascending uptime is descending start
+            SortColumn::Uptime => self.processes.sort_by(|a, b| {
+                if self.sort_ascending {
+                    b.start_time.cmp(&a.start_time)
+                } else {
+                    a.start_time.cmp(&b.start_time)
+                }
+            }),
+            SortColumn::DiskIo => self.processes.sort_by(|a, b| {
+                if self.sort_ascending {
+                    a.disk_io_bytes().cmp(&b.disk_io_bytes())
+                } else {
+                    b.disk_io_bytes().cmp(&a.disk_io_bytes())
+                }
+            }),
         }
     }
 
     pub fn toggle_sort(&mut self, column: SortColumn) {
+        if let Some(optional) = column.optional_column()
+            && !self.shows(optional)
+        {
+            if !optional.is_supported() {
+                return;
+            }
+            // Sorting by a hidden column would be confusing, so show it first
+            self.set_column_visible(optional, true);
+        }
+
         if self.sort_column == column {
             self.sort_ascending = !self.sort_ascending;
         } else {
@@ -101,118 +875,1409 @@ impl App {
         }
         self.sort_processes();
         self.apply_filters();
+        self.log(
+            format!(
+                "Sorted by {} {}",
+                column.name(),
+                if self.sort_ascending {
+                    "ascending"
+                } else {
+                    "descending"
+                }
+            ),
+            Severity::Info,
+        );
     }
 
-    pub fn apply_filters(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_processes = (0..self.processes.len()).collect();
-        } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_processes = self
-                .processes
-                .iter()
-                .enumerate()
-                .filter(|(_, p)| {
-                    p.name.to_lowercase().contains(&query) || p.pid.to_lowercase().contains(&query)
-                })
-                .map(|(i, _)| i)
-                .collect();
+    pub fn shows(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// Show or hide a column, keeping the display order of `Column::ALL`
+    pub fn set_column_visible(&mut self, column: Column, visible: bool) {
+        if !column.is_supported() || self.shows(column) == visible {
+            return;
         }
 
-        // Adjust the selection to be within bounds
-        if let Some(selected) = self.table_state.selected() {
-            if self.filtered_processes.is_empty() {
-                self.table_state.select(None);
-            } else if selected >= self.filtered_processes.len() {
-                self.table_state
-                    .select(Some(self.filtered_processes.len() - 1));
-            }
+        let mut columns: Vec<Column> = self.columns.clone();
+        if visible {
+            columns.push(column);
+        } else {
+            columns.retain(|&c| c != column);
         }
-    }
+        self.columns = Column::ALL
+            .into_iter()
+            .filter(|c| columns.contains(c))
+            .collect();
 
-    pub fn refresh(&mut self) {
-        if self.last_refresh.elapsed() >= Duration::from_secs(REFRESH_RATE) {
-            let selected_pid = self.selected_process().map(|p| p.pid.clone());
-            self.processes = get_system_processes();
+        if !visible && column.sort_column() == Some(self.sort_column) {
+            self.sort_column = SortColumn::Memory;
+            self.sort_ascending = false;
             self.sort_processes();
             self.apply_filters();
-            self.last_refresh = Instant::now();
+        }
+        // Some columns (like FDs) are only collected while visible
+        self.force_refresh();
+    }
 
-            // Try to maintain selection by PID
-            if let Some(pid) = selected_pid {
-                if let Some(index) = self
-                    .filtered_processes
-                    .iter()
-                    .position(|&i| self.processes[i].pid == pid)
-                {
-                    self.table_state.select(Some(index));
-                }
-            }
+    pub fn toggle_fd_column(&mut self) {
+        self.set_column_visible(Column::Fds, !self.shows(Column::Fds));
+    }
+
+    pub fn toggle_threads_column(&mut self) {
+        self.set_column_visible(Column::Threads, !self.shows(Column::Threads));
+    }
+
+    pub fn open_column_menu(&mut self) {
+        self.column_menu_index = 0;
+        self.input_mode = InputMode::ColumnMenu;
+    }
+
+    pub fn column_menu_next(&mut self) {
+        self.column_menu_index = (self.column_menu_index + 1) % Column::ALL.len();
+    }
+
+    pub fn column_menu_previous(&mut self) {
+        self.column_menu_index =
+            (self.column_menu_index + Column::ALL.len() - 1) % Column::ALL.len();
+    }
+
+    /// Show or hide the highlighted column, keeping at least one on screen
+    pub fn toggle_menu_column(&mut self) {
+        let column = Column::ALL[self.column_menu_index];
+        let visible = self.shows(column);
+        if !column.is_supported() || (visible && self.columns.len() == 1) {
+            return;
         }
+        self.set_column_visible(column, !visible);
+        self.custom_columns = true;
+    }
 
-        // Clear message after timeout
-        if let Some(time) = self.message_time {
-            if time.elapsed() > Duration::from_secs(3) {
-                self.message = None;
-                self.message_time = None;
-            }
+    /// The read and write rate columns come and go together
+    pub fn toggle_disk_columns(&mut self) {
+        let visible = !self.shows(Column::DiskRead);
+        self.set_column_visible(Column::DiskRead, visible);
+        self.set_column_visible(Column::DiskWrite, visible);
+    }
+
+    /// Switch panels, columns and theme to the preset at `index`
+    pub fn apply_preset(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index) else {
+            return;
+        };
+        self.preset_index = index;
+        self.custom_columns = false;
+        self.details_height = preset.details_height;
+        self.show_threads = preset.threads && THREADS_SUPPORTED;
+        self.theme = Theme::detect_named(preset.theme.as_deref());
+
+        let columns = preset.columns.clone();
+        for column in Column::ALL {
+            self.set_column_visible(column, columns.contains(&column));
         }
     }
 
-    pub fn next(&mut self) {
-        if self.filtered_processes.is_empty() {
+    /// Switch to the next built-in theme until the preset changes it again
+    pub fn cycle_theme(&mut self) {
+        if colors_disabled() {
+            self.set_message(
+                "Colors are turned off by NO_COLOR or TERM".to_string(),
+                Severity::Error,
+            );
             return;
         }
-        let i = match self.table_state.selected() {
-            Some(i) => (i + 1) % self.filtered_processes.len(),
-            None => 0,
-        };
-        self.table_state.select(Some(i));
+        self.theme = self.theme.next();
+        let name = self.theme.name;
+        self.set_message(format!("Theme: {name}"), Severity::Success);
     }
 
-    pub fn previous(&mut self) {
-        if self.filtered_processes.is_empty() {
+    pub fn next_preset(&mut self) {
+        if self.presets.is_empty() {
             return;
         }
-        let i = match self.table_state.selected() {
-            Some(i) => (i + self.filtered_processes.len() - 1) % self.filtered_processes.len(),
-            None => 0,
-        };
-        self.table_state.select(Some(i));
+        self.apply_preset((self.preset_index + 1) % self.presets.len());
+        let name = self.presets[self.preset_index].name.clone();
+        self.set_message(format!("Layout: {name}"), Severity::Success);
     }
 
-    pub fn kill_selected_process(&mut self) {
-        if let Some(process) = self.selected_process() {
-            let pid = process.pid.parse::<u32>().unwrap_or(0);
-            if pid > 0 {
-                #[cfg(target_os = "windows")]
-                let kill_result = Command::new("taskkill")
-                    .args(["/F", "/PID", &process.pid])
-                    .output();
-
-                #[cfg(not(target_os = "windows"))]
-                let kill_result = Command::new("kill").arg(&process.pid).output();
-
-                match kill_result {
-                    Ok(_) => {
-                        self.set_message(format!("Process {} killed", process.name), Color::Green);
-                        // Immediately refresh process list
-                        self.last_refresh = Instant::now()
-                            .checked_sub(Duration::from_secs(REFRESH_RATE + 1))
-                            .unwrap_or(Instant::now());
-                    }
-                    Err(e) => {
-                        self.set_message(format!("Failed to kill process: {}", e), Color::Red);
-                    }
+    pub fn apply_filters(&mut self) {
+        let query = self.search_query.to_lowercase();
+        let status_filter = self.status_filter;
+        let min_memory_mb = self.min_memory_mb;
+        let hide_kernel_threads = self.hide_kernel_threads;
+        let processes = &self.processes;
+        let mut hidden_kernel_threads = 0;
+        let user_filter = self.user_filter.as_deref();
+        let regex_mode = self.search_regex.update(&self.search_query);
+        let regex = &self.search_regex;
+        let fuzzy = self.fuzzy_search && !query.is_empty() && !regex_mode;
+        let mut scored: Vec<(usize, i64)> = self
+            .processes
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| status_filter.matches(p))
+            .filter(|(_, p)| min_memory_mb.is_none_or(|min| p.memory_mb >= min))
+            .filter(|(_, p)| match (user_filter, &p.user) {
+                (Some(wanted), Some(user)) => user == wanted,
+                _ => true,
+            })
+            .filter_map(|(i, p)| {
+                if query.is_empty() || p.pid.to_string().starts_with(&query) {
+                    Some((i, 0))
+                } else if regex_mode {
+                    regex.is_match(&p.name).then_some((i, 0))
+                } else if fuzzy {
+                    fuzzy_score(&query, &p.name).map(|score| (i, score))
+                } else {
+                    p.name.to_lowercase().contains(&query).then_some((i, 0))
                 }
+            })
+            // Last, so the count only covers kernel threads that would otherwise be shown
+            .filter(|&(i, _)| {
+                let hide = hide_kernel_threads && is_kernel_thread(&processes[i]);
+                hidden_kernel_threads += usize::from(hide);
+                !hide
+            })
+            .collect();
+        if fuzzy {
+            // Stable, so equally good matches keep the column sort order
+            scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        }
+        if !self.pinned.is_empty() {
+            // Also stable, the pinned ones keep the order above among themselves
+            scored.sort_by_key(|&(i, _)| !self.pinned.contains(&self.processes[i].pid));
+        }
+        self.filtered_processes = scored.into_iter().map(|(i, _)| i).collect();
+        self.hidden_kernel_threads = hidden_kernel_threads;
+        // Never kill something the user can't see anymore
+        let visible: HashSet<Pid> = self
+            .filtered_processes
+            .iter()
+            .map(|&i| self.processes[i].pid)
+            .collect();
+        self.marked.retain(|pid| visible.contains(pid));
+
+        if self.tree_view {
+            self.tree_rows = build_tree(&self.processes, &self.filtered_processes, &self.collapsed);
+            self.filtered_processes = self.tree_rows.iter().map(|row| row.index).collect();
+        } else {
+            self.tree_rows.clear();
+        }
+
+        // Adjust the selection to be within bounds
+        if let Some(selected) = self.table_state.selected() {
+            if self.filtered_processes.is_empty() {
+                self.table_state.select(None);
+            } else if selected >= self.filtered_processes.len() {
+                self.table_state
+                    .select(Some(self.filtered_processes.len() - 1));
             }
+        } else if !self.filtered_processes.is_empty() {
+            // A filter that matched nothing cleared the selection, pick it back up
+            self.table_state.select(Some(0));
         }
-        self.input_mode = InputMode::Normal;
     }
 
-    pub fn set_message(&mut self, message: String, color: Color) {
-        self.message = Some((message, color));
-        self.message_time = Some(Instant::now());
+    /// Byte ranges of `name` the current search matched, so the table can show why a row is there
+    pub fn search_highlights(&self, name: &str) -> Vec<Range<usize>> {
+        if self.search_query.is_empty() {
+            return Vec::new();
+        }
+        if let Some(ranges) = self.search_regex.find_ranges(name) {
+            return ranges;
+        }
+        if self.fuzzy_search {
+            let Some((_, positions)) = fuzzy_match(&self.search_query, name) else {
+                return Vec::new();
+            };
+            return name
+                .char_indices()
+                .enumerate()
+                .filter(|(i, _)| positions.contains(i))
+                .map(|(_, (start, c))| start..start + c.len_utf8())
+                .collect();
+        }
+
+        // Lowercasing can change byte lengths outside ASCII, then the offsets wouldn't line up
+        let lower = name.to_lowercase();
+        if lower.len() != name.len() {
+            return Vec::new();
+        }
+        lower
+            .match_indices(&self.search_query.to_lowercase())
+            .map(|(start, found)| start..start + found.len())
+            .collect()
+    }
+
+    /// Enter in the search popup, the query keeps filtering
+    pub fn finish_search(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.refilter();
+        if !self.search_query.is_empty() {
+            self.log(
+                format!(
+                    "Searched for '{}', {} matches",
+                    self.search_query,
+                    self.filtered_processes.len()
+                ),
+                Severity::Info,
+            );
+        }
+    }
+
+    pub fn toggle_fuzzy_search(&mut self) {
+        self.fuzzy_search = !self.fuzzy_search;
+        self.refilter();
+    }
+
+    /// `apply_filters`, but the selection follows the selected process instead of its row
+    pub fn refilter(&mut self) {
+        let selected_pid = self.selected_process().map(|p| p.pid);
+        self.apply_filters();
+        self.select_pid(selected_pid);
+    }
+
+    pub fn refresh(&mut self) {
+        let mut refreshed = false;
+        if self.last_refresh.elapsed() >= self.refresh_interval {
+            refreshed = true;
+            let started = Instant::now();
+            let selection = self.selection_candidates();
+            let count_fds = self.shows(Column::Fds);
+            let previous: HashSet<Pid> = self.processes.iter().map(|p| p.pid).collect();
+            self.processes = get_system_processes(&mut self.system, &self.users, count_fds);
+            // Same as at startup, a process's first sample has nothing to take a delta from
+            for process in &mut self.processes {
+                if !previous.contains(&process.pid) {
+                    process.disk_read_bytes = 0;
+                    process.disk_written_bytes = 0;
+                }
+            }
+            self.disk_interval = self.disk_sampled.elapsed();
+            self.disk_sampled = Instant::now();
+            self.summary = get_system_summary(&self.system);
+            let live: HashSet<Pid> = self.processes.iter().map(|p| p.pid).collect();
+            self.collapsed.retain(|pid| live.contains(pid));
+            self.pinned.retain(|pid| live.contains(pid));
+            self.track_status_changes();
+            self.update_snapshot_diff();
+            self.sort_processes();
+            self.apply_filters();
+            self.last_refresh = Instant::now();
+            self.debug.refresh_took = started.elapsed();
+
+            // Follow the selected process to its new row, or a neighbour if it exited
+            self.select_first_present(&selection);
+            self.check_pending_kills();
+        }
+
+        self.record_usage(refreshed);
+
+        let selected_pid = self.selected_process().map(|p| p.pid);
+        if selected_pid != self.details_pid {
+            self.details_pid = selected_pid;
+            self.details_scroll = 0;
+        }
+
+        // Threads are only loaded for the selected process, and again whenever it changes
+        if self.show_threads {
+            let selected_pid = self.selected_process().map(|p| p.pid);
+            if refreshed || selected_pid != self.threads_pid {
+                self.update_threads(selected_pid);
+            }
+        }
+
+        // The environment hardly ever changes, so it's only read when the selection does
+        if self.show_environment {
+            let selected_pid = self.selected_process().map(|p| p.pid);
+            if selected_pid != self.environment_pid {
+                self.update_environment(selected_pid);
+            }
+        }
+
+        // Like threads, network counters are only read for the process in the details panel
+        if NET_SUPPORTED && self.details_height > 0 {
+            let selected_pid = self.selected_process().map(|p| p.pid);
+            if refreshed || selected_pid != self.net_pid {
+                self.update_network(selected_pid);
+            }
+        }
+
+        // A confirmation left open shouldn't turn some later keypress into a kill
+        if self.input_mode == InputMode::ConfirmKill
+            && self.kill_time_left() == Some(Duration::ZERO)
+        {
+            self.cancel_kill();
+            self.set_message(
+                "Kill cancelled, the confirmation timed out".to_string(),
+                Severity::Error,
+            );
+        }
+
+        // Clear message after timeout
+        if let Some(time) = self.message_time
+            && time.elapsed() >= MESSAGE_TIMEOUT
+        {
+            self.message = None;
+            self.message_time = None;
+        }
+    }
+
+    /// Sample the selected process on every refresh, and right away when it's newly selected
+    fn record_usage(&mut self, refreshed: bool) {
+        if refreshed {
+            let live: HashSet<Pid> = self.processes.iter().map(|p| p.pid).collect();
+            self.usage_history.retain(|pid, _| live.contains(pid));
+        }
+
+        let Some(process) = self.table_state.selected().and_then(|i| {
+            self.filtered_processes
+                .get(i)
+                .map(|&idx| &self.processes[idx])
+        }) else {
+            return;
+        };
+        match self.usage_history.entry(process.pid) {
+            Entry::Occupied(mut entry) if refreshed => entry.get_mut().push(process),
+            Entry::Occupied(_) => {}
+            Entry::Vacant(entry) => entry.insert(UsageHistory::default()).push(process),
+        }
+    }
+
+    /// Standard, compact, expanded and round again, scrolled back to the top
+    pub fn cycle_details_layout(&mut self) {
+        self.details_layout = self.details_layout.next();
+        self.details_scroll = 0;
+        let label = self.details_layout.label();
+        self.set_message(format!("Details: {label}"), Severity::Success);
+    }
+
+    /// One gauge per core in the summary bar, or a single one for the whole machine
+    pub fn toggle_per_core_cpu(&mut self) {
+        self.per_core_cpu = !self.per_core_cpu;
+    }
+
+    /// Switch between the flat list and children indented under their parents
+    pub fn toggle_tree_view(&mut self) {
+        self.tree_view = !self.tree_view;
+        self.refilter();
+    }
+
+    /// Hide or show everything below the selected process in the tree view
+    pub fn toggle_collapsed(&mut self) {
+        let Some(process) = self.selected_process() else {
+            return;
+        };
+        let pid = process.pid;
+        if !self.collapsed.remove(&pid) {
+            self.collapsed.insert(pid);
+        }
+        self.refilter();
+    }
+
+    /// The selected PID followed by the rows around it, nearest first, for `select_first_present`
+    fn selection_candidates(&self) -> Vec<Pid> {
+        let Some(selected) = self.table_state.selected() else {
+            return Vec::new();
+        };
+        let pid_at = |i: usize| {
+            self.filtered_processes
+                .get(i)
+                .map(|&i| self.processes[i].pid)
+        };
+
+        let mut candidates = Vec::new();
+        for distance in 0..self.filtered_processes.len() {
+            // Below first, so a vanished row hands the selection on like deleting a line does
+            candidates.extend(pid_at(selected + distance));
+            if distance > 0 {
+                candidates.extend(selected.checked_sub(distance).and_then(pid_at));
+            }
+        }
+        candidates
+    }
+
+    /// Select the first of `candidates` that is still listed. If none are, the selection stays
+    /// where `apply_filters` clamped it.
+    fn select_first_present(&mut self, candidates: &[Pid]) {
+        let rows: HashMap<Pid, usize> = self
+            .filtered_processes
+            .iter()
+            .enumerate()
+            .map(|(row, &i)| (self.processes[i].pid, row))
+            .collect();
+        if let Some(&row) = candidates.iter().find_map(|pid| rows.get(pid)) {
+            self.table_state.select(Some(row));
+        }
+    }
+
+    fn select_pid(&mut self, pid: Option<Pid>) {
+        if let Some(pid) = pid
+            && let Some(index) = self
+                .filtered_processes
+                .iter()
+                .position(|&i| self.processes[i].pid == pid)
+        {
+            self.table_state.select(Some(index));
+        }
+    }
+
+    /// `U`: just my processes, or everyone's again
+    pub fn toggle_my_processes(&mut self) {
+        if self.user_filter.is_some() {
+            self.set_user_filter(None);
+            return;
+        }
+        match self.current_user.clone() {
+            Some(user) => self.set_user_filter(Some(user)),
+            None => self.set_message(
+                "Can't tell who the current user is".to_string(),
+                Severity::Error,
+            ),
+        }
+    }
+
+    pub fn set_user_filter(&mut self, user: Option<String>) {
+        self.user_filter = user;
+        self.refilter();
+    }
+
+    pub fn cycle_status_filter(&mut self) {
+        self.status_filter = self.status_filter.next();
+        self.refilter();
+    }
+
+    pub fn toggle_kernel_threads(&mut self) {
+        self.hide_kernel_threads = !self.hide_kernel_threads;
+        self.refilter();
+    }
+
+    /// Step to the next larger memory threshold, and back to off after the largest
+    pub fn cycle_memory_filter(&mut self) {
+        self.min_memory_mb = match self.min_memory_mb {
+            None => self.memory_thresholds_mb.first().copied(),
+            Some(current) => self
+                .memory_thresholds_mb
+                .iter()
+                .copied()
+                .find(|&threshold| threshold > current),
+        };
+        self.refilter();
+    }
+
+    pub fn set_snapshot(&mut self, snapshot: Snapshot) {
+        self.snapshot = Some(snapshot);
+        self.update_snapshot_diff();
+    }
+
+    fn update_snapshot_diff(&mut self) {
+        if let Some(snapshot) = &self.snapshot {
+            self.snapshot_diff = snapshot.compare(&self.processes);
+        }
+    }
+
+    pub fn toggle_threads(&mut self) {
+        if !THREADS_SUPPORTED {
+            return;
+        }
+        self.show_threads = !self.show_threads;
+        self.threads = None;
+        self.threads_pid = None;
+    }
+
+    pub fn toggle_environment(&mut self) {
+        if !ENVIRONMENT_SUPPORTED {
+            return;
+        }
+        self.show_environment = !self.show_environment;
+        self.environment = None;
+        self.environment_pid = None;
+    }
+
+    fn update_environment(&mut self, pid: Option<Pid>) {
+        self.environment = pid.and_then(get_environment).map(|mut variables| {
+            if self.redact_environment {
+                for (name, value) in &mut variables {
+                    if is_secret_variable(name) {
+                        *value = "********".to_string();
+                    }
+                }
+            }
+            variables
+        });
+        self.environment_pid = pid;
+    }
+
+    /// Sample the threads of `pid`, turning the tick difference since the last sample into CPU%
+    fn update_threads(&mut self, pid: Option<Pid>) {
+        let previous = if pid == self.threads_pid {
+            self.threads.take().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let elapsed = self.threads_sampled.elapsed().as_secs_f32();
+
+        self.threads = pid.and_then(get_threads).map(|mut threads| {
+            for thread in &mut threads {
+                if let Some(old) = previous.iter().find(|old| old.tid == thread.tid) {
+                    thread.cpu_usage = thread_cpu_usage(old.cpu_ticks, thread.cpu_ticks, elapsed);
+                }
+            }
+            threads.sort_by(|a, b| {
+                b.cpu_usage
+                    .partial_cmp(&a.cpu_usage)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.tid.cmp(&b.tid))
+            });
+            threads
+        });
+        self.threads_pid = pid;
+        self.threads_sampled = Instant::now();
+    }
+
+    /// Sample the network counters of `pid`, turning the difference since the last sample
+    /// into rates
+    fn update_network(&mut self, pid: Option<Pid>) {
+        let counters = pid.and_then(get_net_counters);
+        let elapsed = self.net_sampled.elapsed();
+
+        self.net_rates = match (self.net_counters, counters) {
+            (Some(old), Some(new)) if pid == self.net_pid => Some((
+                byte_rate(new.received.saturating_sub(old.received), elapsed),
+                byte_rate(new.transmitted.saturating_sub(old.transmitted), elapsed),
+            )),
+            _ => None,
+        };
+        self.net_pid = pid;
+        self.net_counters = counters;
+        self.net_sampled = Instant::now();
+    }
+
+    /// Record status transitions since the last refresh and forget processes that are gone
+    fn track_status_changes(&mut self) {
+        let now = Instant::now();
+        let mut history = HashMap::with_capacity(self.processes.len());
+
+        for process in &self.processes {
+            let entry = match self.status_history.remove(&process.pid) {
+                // A different start time means the PID was reused by a new process
+                Some(entry)
+                    if entry.status == process.status && entry.start_time == process.start_time =>
+                {
+                    entry
+                }
+                _ => StatusSince {
+                    status: process.status,
+                    start_time: process.start_time,
+                    since: now,
+                },
+            };
+            history.insert(process.pid, entry);
+        }
+
+        self.status_history = history;
+    }
+
+    /// How long the process has been in its current status, as far as we have observed
+    pub fn status_duration(&self, process: &ProcessInfo) -> Option<Duration> {
+        self.status_history
+            .get(&process.pid)
+            .map(|entry| entry.since.elapsed())
+    }
+
+    pub fn next(&mut self) {
+        if self.filtered_processes.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) => (i + 1) % self.filtered_processes.len(),
+            None => 0,
+        };
+        self.table_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.filtered_processes.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) => (i + self.filtered_processes.len() - 1) % self.filtered_processes.len(),
+            None => 0,
+        };
+        self.table_state.select(Some(i));
+    }
+
+    /// Select the row under a left click at terminal row `y`, or sort by the header clicked
+    pub fn click(&mut self, column: u16, y: u16) {
+        let area = self.table_area;
+        if y == area.y + 1 {
+            let clicked = self
+                .header_cells
+                .iter()
+                .find(|(cells, _)| cells.contains(&column))
+                .map(|&(_, sort)| sort);
+            if let Some(sort) = clicked {
+                self.toggle_sort(sort);
+            }
+            return;
+        }
+        // Border, header and its bottom margin come before the first row
+        let first_row = area.y + 3;
+        if column < area.x || column >= area.x + area.width || y < first_row {
+            return;
+        }
+        if y >= area.y + area.height.saturating_sub(1) {
+            return;
+        }
+
+        let index = self.table_state.offset() + (y - first_row) as usize;
+        let end = self
+            .page_range()
+            .map_or(self.filtered_processes.len(), |page| page.end);
+        if index >= end {
+            return;
+        }
+        self.table_state.select(Some(index));
+
+        let double = self
+            .last_click
+            .is_some_and(|(row, at)| row == index && at.elapsed() < DOUBLE_CLICK);
+        if double {
+            self.last_click = None;
+            self.begin_kill();
+        } else {
+            self.last_click = Some((index, Instant::now()));
+        }
+    }
+
+    /// The render clamps this to the end of the text
+    pub fn scroll_details_down(&mut self) {
+        self.details_scroll = self
+            .details_scroll
+            .saturating_add(self.details_rows.saturating_sub(1).max(1));
+    }
+
+    pub fn scroll_details_up(&mut self) {
+        self.details_scroll = self
+            .details_scroll
+            .saturating_sub(self.details_rows.saturating_sub(1).max(1));
+    }
+
+    pub fn go_first(&mut self) {
+        if !self.filtered_processes.is_empty() {
+            self.table_state.select(Some(0));
+        }
+    }
+
+    pub fn go_last(&mut self) {
+        if !self.filtered_processes.is_empty() {
+            self.table_state
+                .select(Some(self.filtered_processes.len() - 1));
+        }
+    }
+
+    /// Jump half a screen down without wrapping around like `next` does
+    pub fn half_page_down(&mut self) {
+        if self.filtered_processes.is_empty() {
+            return;
+        }
+        let step = (self.visible_rows / 2).max(1);
+        let i = self.table_state.selected().map_or(0, |i| i + step);
+        self.table_state
+            .select(Some(i.min(self.filtered_processes.len() - 1)));
+    }
+
+    pub fn half_page_up(&mut self) {
+        if self.filtered_processes.is_empty() {
+            return;
+        }
+        let step = (self.visible_rows / 2).max(1);
+        let i = self
+            .table_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(step));
+        self.table_state.select(Some(i));
+    }
+
+    /// Rows on a full page, never more than fit on screen so a page needs no scrolling
+    fn page_rows(&self) -> Option<usize> {
+        (self.page_size > 0).then(|| self.page_size.min(self.visible_rows).max(1))
+    }
+
+    /// At least one page, even when nothing matches
+    pub fn page_count(&self) -> usize {
+        self.page_rows().map_or(1, |rows| {
+            self.filtered_processes.len().div_ceil(rows).max(1)
+        })
+    }
+
+    /// Indices into `filtered_processes` shown on the current page, `None` when not paging
+    pub fn page_range(&self) -> Option<Range<usize>> {
+        let rows = self.page_rows()?;
+        let start = self.current_page * rows;
+        Some(start..(start + rows).min(self.filtered_processes.len()))
+    }
+
+    /// The page follows the selection, so moving past its last row turns the page
+    pub fn apply_paging(&mut self, visible_rows: usize) {
+        self.visible_rows = visible_rows;
+        let Some(rows) = self.page_rows() else {
+            return;
+        };
+        let selected = self.table_state.selected().unwrap_or(0);
+        self.current_page = (selected / rows).min(self.page_count() - 1);
+        *self.table_state.offset_mut() = self.current_page * rows;
+    }
+
+    /// Select the first row of the next page, staying put on the last one
+    pub fn next_page(&mut self) {
+        let Some(rows) = self.page_rows() else {
+            return;
+        };
+        if self.current_page + 1 < self.page_count() {
+            self.current_page += 1;
+            self.table_state.select(Some(self.current_page * rows));
+        }
+    }
+
+    pub fn previous_page(&mut self) {
+        let Some(rows) = self.page_rows() else {
+            return;
+        };
+        if self.current_page > 0 {
+            self.current_page -= 1;
+            self.table_state.select(Some(self.current_page * rows));
+        }
+    }
+
+    /// Move the table offset so `scrolloff` rows stay visible above and below the selection
+    pub fn apply_scrolloff(&mut self, visible_rows: usize) {
+        self.visible_rows = visible_rows;
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        if visible_rows == 0 {
+            return;
+        }
+
+        // Like vim, a scrolloff larger than half the view just keeps the selection centered
+        let margin = self.scrolloff.min(visible_rows.saturating_sub(1) / 2);
+        let mut offset = self.table_state.offset();
+
+        if selected < offset + margin {
+            offset = selected.saturating_sub(margin);
+        } else if selected + margin >= offset + visible_rows {
+            offset = selected + margin + 1 - visible_rows;
+        }
+
+        let max_offset = self.filtered_processes.len().saturating_sub(visible_rows);
+        *self.table_state.offset_mut() = offset.min(max_offset);
+    }
+
+    /// Confirm killing the marked processes or the selected one, by PID so a refresh can't swap it
+    pub fn begin_kill(&mut self) {
+        let processes: Vec<(Pid, String)> = if self.marked.is_empty() {
+            self.selected_process()
+                .map(|process| (process.pid, process.name.clone()))
+                .into_iter()
+                .collect()
+        } else {
+            self.processes
+                .iter()
+                .filter(|process| self.marked.contains(&process.pid))
+                .map(|process| (process.pid, process.name.clone()))
+                .collect()
+        };
+
+        if !processes.is_empty() {
+            self.kill_target = Some(KillTarget::new(processes));
+            self.input_mode = InputMode::ConfirmKill;
+        }
+    }
+
+    pub fn begin_command(&mut self) {
+        self.command_input.clear();
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Run the typed `:` command, reporting anything wrong with it as a message
+    pub fn run_command(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let line = std::mem::take(&mut self.command_input);
+
+        match AppCommand::parse(&line) {
+            Ok(AppCommand::Kill { name, force }) => self.begin_kill_by_name(&name, force),
+            Ok(AppCommand::User { name }) => self.set_user_filter(name),
+            Ok(AppCommand::Jump(pid)) => self.jump_to_pid(pid),
+            Err(e) => self.set_message(e, Severity::Error),
+        }
+    }
+
+    /// Select `pid`, or offer to clear the filters if they are what hides it
+    fn jump_to_pid(&mut self, pid: Pid) {
+        if !self.processes.iter().any(|process| process.pid == pid) {
+            self.set_message(format!("PID {pid} not found"), Severity::Error);
+            return;
+        }
+
+        self.select_pid(Some(pid));
+        if self.selected_process().map(|p| p.pid) != Some(pid) {
+            self.jump_target = Some(pid);
+            self.input_mode = InputMode::ConfirmJump;
+        }
+    }
+
+    /// Drop every filter (and fold in the tree) that could hide a process, then jump again
+    pub fn confirm_jump(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let Some(pid) = self.jump_target.take() else {
+            return;
+        };
+
+        self.search_query.clear();
+        self.status_filter = StatusFilter::All;
+        self.min_memory_mb = None;
+        self.hide_kernel_threads = false;
+        self.user_filter = None;
+        self.collapsed.clear();
+        self.apply_filters();
+        self.select_pid(Some(pid));
+        if self.selected_process().map(|p| p.pid) != Some(pid) {
+            // It exited while the question was up
+            self.set_message(format!("PID {pid} not found"), Severity::Error);
+        }
+    }
+
+    /// Confirm killing every process called exactly `name`
+    fn begin_kill_by_name(&mut self, name: &str, force: bool) {
+        if !force && PROTECTED_NAMES.contains(&name) {
+            self.set_message(
+                format!("Refusing to kill {name}, add --force if you really mean it"),
+                Severity::Error,
+            );
+            return;
+        }
+
+        let processes: Vec<(Pid, String)> = self
+            .processes
+            .iter()
+            .filter(|process| process.name == name)
+            .map(|process| (process.pid, process.name.clone()))
+            .collect();
+        if processes.is_empty() {
+            self.set_message(format!("No process named {name}"), Severity::Error);
+            return;
+        }
+
+        self.kill_target = Some(KillTarget::new(processes));
+        self.input_mode = InputMode::ConfirmKill;
+    }
+
+    /// Add the selected process to the marked set for a bulk kill, or take it out again
+    pub fn toggle_marked(&mut self) {
+        if let Some(pid) = self.selected_process().map(|p| p.pid)
+            && !self.marked.remove(&pid)
+        {
+            self.marked.insert(pid);
+        }
+    }
+
+    /// Keep the selected process at the top of the table, or let it sort normally again
+    pub fn toggle_pinned(&mut self) {
+        let Some((pid, name)) = self
+            .selected_process()
+            .map(|process| (process.pid, process.name.clone()))
+        else {
+            return;
+        };
+
+        if self.pinned.remove(&pid) {
+            self.set_message(format!("Unpinned {name}"), Severity::Success);
+        } else {
+            self.pinned.insert(pid);
+            self.set_message(format!("Pinned {name} to the top"), Severity::Success);
+        }
+        self.refilter();
+    }
+
+    pub fn cancel_kill(&mut self) {
+        self.kill_target = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Time left before the open kill confirmation cancels itself
+    pub fn kill_time_left(&self) -> Option<Duration> {
+        let target = self.kill_target.as_ref()?;
+        let timeout = self.kill_timeout?;
+        Some(timeout.saturating_sub(target.opened.elapsed()))
+    }
+
+    /// Move the signal choice up or down, skipping signals this platform can't send
+    pub fn cycle_kill_signal(&mut self, forward: bool) {
+        let Some(target) = self.kill_target.as_mut() else {
+            return;
+        };
+        let supported: Vec<KillSignal> = KillSignal::ALL
+            .into_iter()
+            .filter(|signal| signal.is_supported())
+            .collect();
+        let current = supported
+            .iter()
+            .position(|&signal| signal == target.signal)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % supported.len()
+        } else {
+            (current + supported.len() - 1) % supported.len()
+        };
+        target.signal = supported[next];
+    }
+
+    pub fn kill_target_process(&mut self) {
+        if self.dry_run
+            && let Some(target) = self.kill_target.take()
+        {
+            let message = match target.processes.as_slice() {
+                [(pid, name)] => {
+                    format!("Would send {} to PID {pid} ({name})", target.signal.name())
+                }
+                processes => format!(
+                    "Would send {} to {} processes",
+                    target.signal.name(),
+                    processes.len()
+                ),
+            };
+            self.set_message(message, Severity::Success);
+            self.marked.clear();
+        } else if let Some(target) = self.kill_target.take() {
+            let signal = target.signal;
+            let results: Vec<(&str, Result<(), String>)> = target
+                .processes
+                .iter()
+                .map(|(pid, name)| (name.as_str(), send_signal(*pid, signal)))
+                .collect();
+            let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+
+            match results.as_slice() {
+                [(name, Ok(()))] => self.set_message(
+                    format!("Sent {} to {}", signal.name(), name),
+                    Severity::Success,
+                ),
+                [(_, Err(e))] => self.set_message(e.clone(), Severity::Error),
+                _ if failed == 0 => self.set_message(
+                    format!("{} {}", signal.past_tense(), results.len()),
+                    Severity::Success,
+                ),
+                _ => self.set_message(
+                    format!(
+                        "{} {}, failed {}",
+                        signal.past_tense(),
+                        results.len() - failed,
+                        failed
+                    ),
+                    Severity::Error,
+                ),
+            }
+            if signal == KillSignal::Term {
+                self.watch_pending_kills(&target.processes, &results);
+            }
+            if failed < results.len() {
+                self.force_refresh();
+            }
+            self.marked.clear();
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Start the grace period of every process that SIGTERM was sent to
+    fn watch_pending_kills(
+        &mut self,
+        processes: &[(Pid, String)],
+        results: &[(&str, Result<(), String>)],
+    ) {
+        let deadline = Instant::now() + TERM_GRACE;
+        for ((pid, name), (_, result)) in processes.iter().zip(results) {
+            let Some(process) = self.processes.iter().find(|p| p.pid == *pid) else {
+                continue;
+            };
+            if result.is_ok() {
+                self.pending_kills.insert(
+                    *pid,
+                    PendingKill {
+                        name: name.clone(),
+                        start_time: process.start_time,
+                        deadline,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Report the processes that exited after SIGTERM, and offer SIGKILL for the ones
+    /// still running past their grace period
+    fn check_pending_kills(&mut self) {
+        if self.pending_kills.is_empty() {
+            return;
+        }
+
+        let running: HashMap<Pid, u64> = self
+            .processes
+            .iter()
+            .map(|process| (process.pid, process.start_time))
+            .collect();
+        let mut exited: Vec<String> = Vec::new();
+        self.pending_kills.retain(|pid, pending| {
+            let alive = running.get(pid) == Some(&pending.start_time);
+            if !alive {
+                exited.push(pending.name.clone());
+            }
+            alive
+        });
+        match exited.as_slice() {
+            [] => {}
+            [name] => self.set_message(format!("{name} exited cleanly"), Severity::Success),
+            names => self.set_message(
+                format!("{} processes exited cleanly", names.len()),
+                Severity::Success,
+            ),
+        }
+
+        // Don't pull the prompt over whatever the user is in the middle of, it waits
+        if self.input_mode != InputMode::Normal {
+            return;
+        }
+        let now = Instant::now();
+        let mut overdue: Vec<(Pid, String)> = self
+            .pending_kills
+            .iter()
+            .filter(|(_, pending)| pending.deadline <= now)
+            .map(|(pid, pending)| (*pid, pending.name.clone()))
+            .collect();
+        if overdue.is_empty() {
+            return;
+        }
+        overdue.sort();
+        for (pid, _) in &overdue {
+            self.pending_kills.remove(pid);
+        }
+
+        let mut target = KillTarget::new(overdue);
+        target.signal = KillSignal::Kill;
+        target.escalation = true;
+        self.kill_target = Some(target);
+        self.input_mode = InputMode::ConfirmKill;
+    }
+
+    /// Open the renice popup for the selected process, starting from its current nice value
+    #[cfg(unix)]
+    pub fn begin_renice(&mut self) {
+        if let Some(process) = self.selected_process() {
+            self.renice_target = Some(ReniceTarget {
+                pid: process.pid,
+                name: process.name.clone(),
+                input: get_priority(process.pid).to_string(),
+            });
+            self.input_mode = InputMode::Renice;
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn cancel_renice(&mut self) {
+        self.renice_target = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    #[cfg(unix)]
+    pub fn apply_renice(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let Some(target) = self.renice_target.take() else {
+            return;
+        };
+
+        match target.input.trim().parse::<i32>() {
+            Ok(nice) if (-20..=19).contains(&nice) && self.dry_run => self.set_message(
+                format!("Would set nice of {} to {nice}", target.name),
+                Severity::Success,
+            ),
+            Ok(nice) if (-20..=19).contains(&nice) => match set_priority(target.pid, nice) {
+                Ok(()) => {
+                    self.set_message(
+                        format!("Set nice of {} to {nice}", target.name),
+                        Severity::Success,
+                    );
+                    self.force_refresh();
+                }
+                Err(e) => {
+                    self.set_message(format!("Failed to renice: {e}"), Severity::Error);
+                }
+            },
+            _ => self.set_message(
+                format!("Nice must be between -20 and 19, not '{}'", target.input),
+                Severity::Error,
+            ),
+        }
+    }
+
+    /// SIGSTOP the selected process, or SIGCONT it if it is already stopped
+    #[cfg(unix)]
+    pub fn toggle_frozen(&mut self) {
+        let Some(&index) = self
+            .table_state
+            .selected()
+            .and_then(|i| self.filtered_processes.get(i))
+        else {
+            return;
+        };
+        let process = &mut self.processes[index];
+        if process.pid.as_u32() == 0 {
+            return;
+        }
+
+        let frozen = process.status == ProcessStatus::Stop;
+        let name = format!("{} ({})", process.name, process.pid);
+        if self.dry_run {
+            let action = if frozen { "resume" } else { "freeze" };
+            self.set_message(format!("Would {action} {name}"), Severity::Success);
+            return;
+        }
+
+        let (result, action, status) = if frozen {
+            (resume_process(process.pid), "Resumed", ProcessStatus::Run)
+        } else {
+            (suspend_process(process.pid), "Froze", ProcessStatus::Stop)
+        };
+
+        match result {
+            Ok(()) => {
+                // Show the new state right away instead of after the next refresh
+                process.status = status;
+                self.set_message(format!("{action} {name}"), Severity::Success);
+            }
+            Err(e) => self.set_message(
+                format!(
+                    "Failed to {} {name}: {e}",
+                    if frozen { "resume" } else { "freeze" }
+                ),
+                Severity::Error,
+            ),
+        }
+    }
+
+    /// Show the folder holding the selected process's executable in the file manager
+    pub fn open_exe_folder(&mut self) {
+        let Some(process) = self.selected_process() else {
+            return;
+        };
+        let name = process.name.clone();
+        let Some(folder) = process
+            .exe_path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+        else {
+            self.set_message(format!("No executable path for {name}"), Severity::Error);
+            return;
+        };
+
+        match open_folder(&folder) {
+            Ok(()) => self.set_message(format!("Opened {}", folder.display()), Severity::Success),
+            Err(e) => self.set_message(
+                format!("Failed to open {}: {e}", folder.display()),
+                Severity::Error,
+            ),
+        }
+    }
+
+    /// Something the user set up that quitting would throw away
+    pub fn busy_activity(&self) -> Option<&'static str> {
+        if !self.search_query.is_empty() {
+            Some("A search filter is active")
+        } else if self.status_filter != StatusFilter::All {
+            Some("A status filter is active")
+        } else if self.min_memory_mb.is_some() {
+            Some("A memory filter is active")
+        } else if self.user_filter.is_some() {
+            Some("A user filter is active")
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the app should exit now, otherwise asks for confirmation first
+    pub fn request_quit(&mut self) -> bool {
+        let ask = match self.confirm_quit {
+            QuitConfirm::Never => false,
+            QuitConfirm::WhenBusy => self.busy_activity().is_some(),
+            QuitConfirm::Always => true,
+        };
+        if ask {
+            self.input_mode = InputMode::ConfirmQuit;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Remember the sort order and refresh interval for the next run
+    pub fn save_preferences(&self) -> std::io::Result<()> {
+        Preferences {
+            sort_column: self.sort_column,
+            sort_ascending: self.sort_ascending,
+            refresh_interval_ms: self.refresh_interval.as_millis() as u64,
+            columns: self.custom_columns.then(|| self.columns.clone()),
+        }
+        .save()
+    }
+
+    /// Save the processes currently shown to `processes-<unix time>.json` in the working directory
+    pub fn export_json(&mut self) {
+        self.export("json", export_snapshot);
+    }
+
+    /// Like `export_json`, but only the visible columns, as `processes-<unix time>.csv`
+    pub fn export_csv(&mut self) {
+        let columns = self.columns.clone();
+        let disk_interval = self.disk_interval;
+        self.export("csv", |shown, path| {
+            export_snapshot_csv(shown, &columns, disk_interval, path)
+        });
+    }
+
+    fn export(
+        &mut self,
+        extension: &str,
+        write: impl FnOnce(&[&ProcessInfo], &Path) -> anyhow::Result<()>,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = PathBuf::from(format!("processes-{timestamp}.{extension}"));
+        let shown: Vec<&ProcessInfo> = self
+            .filtered_processes
+            .iter()
+            .map(|&i| &self.processes[i])
+            .collect();
+
+        match write(&shown, &path) {
+            Ok(()) => self.set_message(
+                format!("Exported {} processes to {}", shown.len(), path.display()),
+                Severity::Success,
+            ),
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    /// How long the main loop may sleep before the next refresh or message timeout is due
+    pub fn next_wakeup(&self) -> Duration {
+        let mut wakeup = self
+            .refresh_interval
+            .saturating_sub(self.last_refresh.elapsed());
+        if let Some(time) = self.message_time {
+            wakeup = wakeup.min(MESSAGE_TIMEOUT.saturating_sub(time.elapsed()));
+        }
+        // Wake on each whole second so the countdown in the title ticks
+        if self.input_mode == InputMode::ConfirmKill
+            && let Some(left) = self.kill_time_left()
+        {
+            let to_next_second = Duration::from_nanos(u64::from(left.subsec_nanos()));
+            wakeup = wakeup.min(if to_next_second.is_zero() {
+                left.min(Duration::from_secs(1))
+            } else {
+                to_next_second
+            });
+        }
+        wakeup
+    }
+
+    /// Make the next `refresh` re-query the process list immediately
+    pub fn force_refresh(&mut self) {
+        self.last_refresh = Instant::now()
+            .checked_sub(self.refresh_interval)
+            .unwrap_or(Instant::now());
+    }
+
+    /// Refresh less often, in steps of half a second
+    pub fn slower_refresh(&mut self) {
+        self.refresh_interval = (self.refresh_interval + REFRESH_INTERVAL_STEP)
+            .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL);
+    }
+
+    /// Refresh more often, in steps of half a second
+    pub fn faster_refresh(&mut self) {
+        self.refresh_interval = self
+            .refresh_interval
+            .saturating_sub(REFRESH_INTERVAL_STEP)
+            .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL);
+    }
+
+    pub fn begin_set_interval(&mut self) {
+        self.interval_input = self.refresh_interval.as_millis().to_string();
+        self.interval_error = None;
+        self.input_mode = InputMode::SetInterval;
+    }
+
+    /// Use the typed interval, or keep the popup open with the reason it can't be used
+    pub fn apply_interval(&mut self) {
+        let (min, max) = (
+            MIN_REFRESH_INTERVAL.as_millis() as u64,
+            MAX_REFRESH_INTERVAL.as_millis() as u64,
+        );
+        match self.interval_input.trim().parse::<u64>() {
+            Ok(ms) if (min..=max).contains(&ms) => {
+                self.refresh_interval = Duration::from_millis(ms);
+                self.input_mode = InputMode::Normal;
+                self.set_message(format!("Refreshing every {ms} ms"), Severity::Success);
+            }
+            Ok(_) => self.interval_error = Some(format!("must be {min} to {max}")),
+            Err(_) => self.interval_error = Some("not a number".to_string()),
+        }
+    }
+
+    pub fn set_message(&mut self, message: String, severity: Severity) {
+        self.log(message.clone(), severity);
+        self.message = Some((message, severity));
+        self.message_time = Some(Instant::now());
+    }
+
+    /// Record something in the log without showing it
+    pub fn log(&mut self, text: String, severity: Severity) {
+        if self.log.len() == LOG_LEN {
+            self.log.pop_front();
+        }
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.log.push_back(LogEntry { at, text, severity });
+    }
+
+    pub fn open_log(&mut self) {
+        self.log_scroll = 0;
+        self.input_mode = InputMode::Log;
+    }
+
+    /// Towards older entries, stopping at the first one
+    pub fn scroll_log_up(&mut self, lines: usize) {
+        self.log_scroll = (self.log_scroll + lines).min(self.log.len().saturating_sub(1));
+    }
+
+    pub fn scroll_log_down(&mut self, lines: usize) {
+        self.log_scroll = self.log_scroll.saturating_sub(lines);
+    }
+
+    /// Show a recoverable error, with its whole chain of causes, instead of quitting over it
+    pub fn report_error(&mut self, err: &anyhow::Error) {
+        self.set_message(format!("{err:#}"), Severity::Error);
+    }
+
+    /// The configured command with `{pid}` and `{name}` filled in for the selected process
+    pub fn selected_command(&self) -> Option<String> {
+        let template = self.custom_command.as_ref()?;
+        let process = self.selected_process()?;
+        Some(
+            template
+                .replace("{pid}", &process.pid.to_string())
+                .replace("{name}", &shell_quote(&process.name)),
+        )
     }
 
     pub fn selected_process(&self) -> Option<&ProcessInfo> {
@@ -222,4 +2287,396 @@ impl App {
                 .map(|&idx| &self.processes[idx])
         })
     }
-}
\ No newline at end of file
+}
+
+/// A search query written as `/pattern/`, matched case-insensitively against process names
+#[derive(Default)]
+pub struct SearchRegex {
+    /// The last pattern that compiled, still used while the current one doesn't
+    #[cfg(feature = "regex")]
+    regex: Option<regex::Regex>,
+    /// The current pattern doesn't compile
+    pub invalid: bool,
+}
+
+impl SearchRegex {
+    /// Compile `query` if it is wrapped in slashes, returns whether it is
+    #[cfg(feature = "regex")]
+    fn update(&mut self, query: &str) -> bool {
+        let Some(pattern) = query.strip_prefix('/').and_then(|q| q.strip_suffix('/')) else {
+            self.regex = None;
+            self.invalid = false;
+            return false;
+        };
+
+        match regex::RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+        {
+            Ok(regex) => {
+                self.regex = Some(regex);
+                self.invalid = false;
+            }
+            // Half-typed patterns fail all the time, keep filtering by the last good one
+            Err(_) => self.invalid = true,
+        }
+        true
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn update(&mut self, _query: &str) -> bool {
+        false
+    }
+
+    /// Until a pattern has compiled, everything matches
+    #[cfg(feature = "regex")]
+    fn is_match(&self, name: &str) -> bool {
+        self.regex.as_ref().is_none_or(|regex| regex.is_match(name))
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn is_match(&self, _name: &str) -> bool {
+        true
+    }
+
+    /// Byte ranges of every match in `name`, `None` unless a pattern is in use
+    #[cfg(feature = "regex")]
+    fn find_ranges(&self, name: &str) -> Option<Vec<Range<usize>>> {
+        let regex = self.regex.as_ref()?;
+        Some(regex.find_iter(name).map(|m| m.range()).collect())
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn find_ranges(&self, _name: &str) -> Option<Vec<Range<usize>>> {
+        None
+    }
+}
+
+/// How well `query` matches `candidate` as a case-insensitive subsequence, higher is better.
+/// `None` if some character of the query doesn't appear in order.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    fuzzy_match(query, candidate).map(|(score, _)| score)
+}
+
+/// The score and the char indices of `candidate` that matched, for highlighting
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut next = 0;
+
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let found = (next..candidate.len())
+            .find(|&i| candidate[i].to_lowercase().eq(std::iter::once(q)))?;
+
+        score += 1;
+        if found == 0 || !candidate[found - 1].is_alphanumeric() {
+            score += 3; // Start of a word
+        }
+        if found > 0 && positions.last() == Some(&(found - 1)) {
+            score += 5; // Right after the previous match
+        }
+        score -= (found - next).min(5) as i64; // Skipped characters, capped so long names aren't buried
+
+        positions.push(found);
+        next = found + 1;
+    }
+
+    Some((score, positions))
+}
+
+/// Hand `folder` to the desktop's file manager
+#[cfg(feature = "opener")]
+fn open_folder(folder: &Path) -> Result<(), String> {
+    opener::open(folder).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "opener"))]
+fn open_folder(_folder: &Path) -> Result<(), String> {
+    Err("built without the `opener` feature".to_string())
+}
+
+/// Send `signal` to `pid`, the error is ready to show as a message
+fn send_signal(pid: Pid, signal: KillSignal) -> Result<(), String> {
+    if pid.as_u32() == 0 {
+        return Err("Refusing to signal PID 0".to_string());
+    }
+    let pid = pid.to_string();
+
+    #[cfg(target_os = "windows")]
+    let kill_result = match signal {
+        KillSignal::Kill => Command::new("taskkill").args(["/F", "/PID", &pid]).output(),
+        _ => Command::new("taskkill").args(["/PID", &pid]).output(),
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let kill_result = Command::new("kill")
+        .arg(format!("-{}", signal.name().trim_start_matches("SIG")))
+        .arg(&pid)
+        .output();
+
+    match kill_result {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            Err(format!("Failed to signal process: {}", error.trim()))
+        }
+        Err(e) => Err(format!("Failed to kill process: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An app listing just `processes`, sorted and filtered with nothing hidden
+    fn app_with(processes: Vec<ProcessInfo>) -> App {
+        let mut app = App::new(&Config::default(), Preferences::default());
+        app.hide_kernel_threads = false;
+        app.processes = processes;
+        app.sort_processes();
+        app.apply_filters();
+        app.table_state.select(Some(0));
+        app
+    }
+
+    /// What `refresh` does with a new process list, minus asking the system for it
+    fn refresh_with(app: &mut App, processes: Vec<ProcessInfo>) {
+        let selection = app.selection_candidates();
+        app.processes = processes;
+        app.sort_processes();
+        app.apply_filters();
+        app.select_first_present(&selection);
+    }
+
+    #[test]
+    fn kill_confirmation_keeps_its_target_across_a_refresh() {
+        let mut app = app_with(vec![
+            ProcessInfo::sample(10, "editor", 300.0),
+            ProcessInfo::sample(20, "browser", 200.0),
+        ]);
+        app.dry_run = true;
+        assert_eq!(app.selected_process().map(|p| p.pid.as_u32()), Some(10));
+
+        app.begin_kill();
+        // The browser grows past the editor and takes over the selected row
+        refresh_with(
+            &mut app,
+            vec![
+                ProcessInfo::sample(10, "editor", 300.0),
+                ProcessInfo::sample(20, "browser", 900.0),
+            ],
+        );
+        app.table_state.select(Some(0));
+        assert_eq!(app.selected_process().map(|p| p.pid.as_u32()), Some(20));
+        app.kill_target_process();
+
+        let (message, _) = app
+            .message
+            .expect("a dry run reports what it would have done");
+        assert_eq!(message, "Would send SIGTERM to PID 10 (editor)");
+    }
+
+    #[test]
+    fn pids_sort_as_numbers() {
+        let mut app = app_with(
+            [100, 2, 10]
+                .map(|pid| ProcessInfo::sample(pid, "worker", 1.0))
+                .into(),
+        );
+        app.sort_column = SortColumn::Pid;
+        app.sort_ascending = true;
+        app.sort_processes();
+
+        let pids: Vec<u32> = app.processes.iter().map(|p| p.pid.as_u32()).collect();
+        assert_eq!(pids, [2, 10, 100]);
+    }
+
+    #[test]
+    fn search_ignores_case_and_matches_pid_prefixes() {
+        let mut app = app_with(vec![
+            ProcessInfo::sample(12345, "bash", 1.0),
+            ProcessInfo::sample(777, "Firefox", 2.0),
+        ]);
+        let matches = |app: &mut App, query: &str| {
+            app.search_query = query.to_string();
+            app.apply_filters();
+            app.filtered_processes
+                .iter()
+                .map(|&i| app.processes[i].name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matches(&mut app, "fire"), ["Firefox"]);
+        assert_eq!(matches(&mut app, "FIRE"), ["Firefox"]);
+        assert_eq!(matches(&mut app, "123"), ["bash"]);
+        assert!(matches(&mut app, "345").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_scores_rank_closer_matches_higher() {
+        assert_eq!(fuzzy_score("xyz", "firefox"), None);
+        assert_eq!(
+            fuzzy_score("fxo", "firefox"),
+            None,
+            "characters must appear in order"
+        );
+        assert!(fuzzy_score("", "anything").is_some());
+        assert_eq!(fuzzy_score("FF", "firefox"), fuzzy_score("ff", "firefox"));
+
+        // Consecutive characters beat the same ones spread out
+        assert!(fuzzy_score("fire", "firefox") > fuzzy_score("fire", "f-i-r-e"));
+        // Word starts beat the middle of a word
+        assert!(fuzzy_score("gs", "gnome-shell") > fuzzy_score("gs", "gnomes"));
+        // Skipped characters cost points, but only up to a cap
+        assert!(fuzzy_score("fo", "fox") > fuzzy_score("fo", "firefox"));
+        assert_eq!(
+            fuzzy_score("ab", "a1234567b"),
+            fuzzy_score("ab", "a123456789b")
+        );
+    }
+
+    #[test]
+    fn fuzzy_search_lists_the_best_match_first() {
+        let mut app = app_with(vec![
+            ProcessInfo::sample(1, "cargo-watch", 3.0),
+            ProcessInfo::sample(2, "code", 2.0),
+            ProcessInfo::sample(3, "bash", 1.0),
+        ]);
+        app.fuzzy_search = true;
+        app.search_query = "co".to_string();
+        app.apply_filters();
+
+        let names: Vec<&str> = app
+            .filtered_processes
+            .iter()
+            .map(|&i| app.processes[i].name.as_str())
+            .collect();
+        assert_eq!(names, ["code", "cargo-watch"]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_queries_filter_by_pattern() {
+        let mut regex = SearchRegex::default();
+        assert!(regex.update("/PY.*3/"));
+        assert!(!regex.invalid);
+        assert!(regex.is_match("python3"));
+        assert!(!regex.is_match("bash"));
+        assert!(regex.update("/PY/"));
+        assert_eq!(
+            regex.find_ranges("python3, pypy"),
+            Some(vec![0..2, 9..11, 11..13])
+        );
+
+        assert!(!regex.update("py.*3"), "only slashes make a pattern");
+        assert_eq!(regex.find_ranges("python3"), None);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn invalid_regex_keeps_the_last_good_pattern() {
+        let mut app = app_with(vec![
+            ProcessInfo::sample(1, "python3", 2.0),
+            ProcessInfo::sample(2, "bash", 1.0),
+        ]);
+        app.search_query = "/py/".to_string();
+        app.apply_filters();
+        assert_eq!(app.filtered_processes.len(), 1);
+
+        app.search_query = "/py(/".to_string();
+        app.apply_filters();
+        assert!(app.search_regex.invalid);
+        assert_eq!(app.filtered_processes.len(), 1);
+        assert_eq!(app.processes[app.filtered_processes[0]].name, "python3");
+    }
+
+    #[cfg(not(feature = "regex"))]
+    #[test]
+    fn regex_queries_are_plain_text_without_the_feature() {
+        let mut regex = SearchRegex::default();
+        assert!(!regex.update("/py.*3/"));
+        assert!(!regex.update("/py(/"));
+        assert!(!regex.invalid);
+        assert_eq!(regex.find_ranges("python3"), None);
+
+        let mut app = app_with(vec![ProcessInfo::sample(1, "python3", 1.0)]);
+        app.search_query = "/py.*3/".to_string();
+        app.apply_filters();
+        assert!(app.filtered_processes.is_empty());
+    }
+
+    #[test]
+    fn selection_survives_processes_vanishing() {
+        let processes = |pids: &[u32]| {
+            pids.iter()
+                .map(|&pid| ProcessInfo::sample(pid, "worker", 1.0))
+                .collect()
+        };
+        let mut app = app_with(processes(&[1, 2, 3, 4]));
+        app.sort_column = SortColumn::Pid;
+        app.sort_ascending = true;
+        app.sort_processes();
+        app.apply_filters();
+        let selected = |app: &App| app.selected_process().map(|p| p.pid.as_u32());
+
+        // The selected process follows its PID to a new row
+        app.table_state.select(Some(2));
+        refresh_with(&mut app, processes(&[1, 3, 4]));
+        assert_eq!(selected(&app), Some(3));
+
+        // Then hands over to the row below when it exits
+        refresh_with(&mut app, processes(&[1, 4]));
+        assert_eq!(selected(&app), Some(4));
+
+        // Or the one above when it was the last row
+        refresh_with(&mut app, processes(&[1]));
+        assert_eq!(app.table_state.selected(), Some(0));
+        assert_eq!(selected(&app), Some(1));
+
+        refresh_with(&mut app, Vec::new());
+        assert_eq!(app.table_state.selected(), None);
+    }
+
+    #[test]
+    fn scrolling_keeps_the_selection_inside_the_window() {
+        const VISIBLE_ROWS: usize = 5;
+        let mut app = app_with(
+            (1..=20)
+                .map(|pid| ProcessInfo::sample(pid, "worker", 1.0))
+                .collect(),
+        );
+        app.scrolloff = 2;
+        let len = app.filtered_processes.len();
+        let check = |app: &App| {
+            let selected = app.table_state.selected().unwrap();
+            let offset = app.table_state.offset();
+            assert!(offset <= len - VISIBLE_ROWS, "offset {offset} past the end");
+            // The row index the table is handed, relative to the rows actually built
+            let window_selected = selected.checked_sub(offset);
+            assert!(
+                window_selected.is_some_and(|row| row < VISIBLE_ROWS),
+                "row {selected} is outside the window starting at {offset}"
+            );
+        };
+
+        // What every draw does before building the visible rows
+        app.apply_scrolloff(VISIBLE_ROWS);
+        check(&app);
+        for _ in 1..len {
+            app.next();
+            app.apply_scrolloff(VISIBLE_ROWS);
+            check(&app);
+        }
+        assert_eq!(app.table_state.selected(), Some(len - 1));
+        assert_eq!(app.table_state.offset(), len - VISIBLE_ROWS);
+
+        for _ in 1..len {
+            app.previous();
+            app.apply_scrolloff(VISIBLE_ROWS);
+            check(&app);
+        }
+        assert_eq!(app.table_state.selected(), Some(0));
+        assert_eq!(app.table_state.offset(), 0);
+    }
+}
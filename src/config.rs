@@ -0,0 +1,369 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
+
+use crate::app::{Column, DEFAULT_REFRESH_INTERVAL, SortColumn};
+
+/// User settings read from `config.toml` in the platform config directory
+pub struct Config {
+    /// Rows of context kept above/below the selection while scrolling (like vim's `scrolloff`)
+    pub scrolloff: usize,
+    /// Rows per page for the paged table, 0 scrolls one long list instead
+    pub page_size: usize,
+    /// Shell command run against the selected process, `{pid}` and `{name}` are substituted
+    pub command: Option<String>,
+    /// When to ask before quitting
+    pub confirm_quit: QuitConfirm,
+    /// Seconds before an unanswered kill confirmation cancels itself, 0 never does
+    pub kill_confirm_timeout_secs: u64,
+    /// Start with kernel threads hidden, `K` still shows them
+    pub hide_kernel_threads: bool,
+    /// Minimum-memory filter steps in MB that `M` cycles through after "off", ascending
+    pub memory_thresholds_mb: Vec<f64>,
+    /// Hide the values of environment variables whose names look like secrets
+    pub redact_environment: bool,
+    /// Where names too long for their column are cut
+    pub name_truncation: NameTruncation,
+    /// Named layouts, always starting with the built-in `default`
+    pub presets: Vec<LayoutPreset>,
+    /// The preset to start with
+    pub preset: Option<String>,
+    /// Theme for presets that don't pick their own
+    pub theme: Option<String>,
+}
+
+/// `confirm_quit = true | false | "always"`
+#[derive(PartialEq, Copy, Clone)]
+pub enum QuitConfirm {
+    Never,
+    /// Only while something stateful (like an active filter) would be lost
+    WhenBusy,
+    Always,
+}
+
+impl<'de> Deserialize<'de> for QuitConfirm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Bool(bool),
+            Name(String),
+        }
+
+        match Value::deserialize(deserializer)? {
+            Value::Bool(false) => Ok(QuitConfirm::Never),
+            Value::Bool(true) => Ok(QuitConfirm::WhenBusy),
+            Value::Name(name) if name == "always" => Ok(QuitConfirm::Always),
+            Value::Name(name) => Err(D::Error::custom(format!(
+                "invalid confirm_quit \"{name}\", expected true, false or \"always\""
+            ))),
+        }
+    }
+}
+
+/// `name_truncation = "end" | "middle"`
+#[derive(PartialEq, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameTruncation {
+    End,
+    /// Keep the start and the end, for names that only differ at the end
+    Middle,
+}
+
+/// Which panels and columns are shown, switched as a whole with `L`
+#[derive(Clone)]
+pub struct LayoutPreset {
+    pub name: String,
+    /// Rows for the details panel, 0 hides it
+    pub details_height: u16,
+    pub columns: Vec<Column>,
+    pub threads: bool,
+    pub theme: Option<String>,
+}
+
+impl Default for LayoutPreset {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            details_height: 10,
+            columns: vec![
+                Column::Pid,
+                Column::Status,
+                Column::Name,
+                Column::Cpu,
+                Column::Memory,
+            ],
+            threads: false,
+            theme: None,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            scrolloff: 0,
+            page_size: 0,
+            command: None,
+            confirm_quit: QuitConfirm::WhenBusy,
+            kill_confirm_timeout_secs: 10,
+            hide_kernel_threads: false,
+            memory_thresholds_mb: vec![100.0, 500.0, 1024.0],
+            redact_environment: true,
+            name_truncation: NameTruncation::End,
+            presets: vec![LayoutPreset::default()],
+            preset: None,
+            theme: None,
+        }
+    }
+}
+
+/// `config.toml` as written. Every key is optional, the missing ones keep their defaults.
+#[derive(Deserialize)]
+struct ConfigFile {
+    scrolloff: Option<usize>,
+    page_size: Option<usize>,
+    command: Option<String>,
+    confirm_quit: Option<QuitConfirm>,
+    kill_confirm_timeout_secs: Option<u64>,
+    hide_kernel_threads: Option<bool>,
+    memory_thresholds: Option<String>,
+    redact_environment: Option<bool>,
+    name_truncation: Option<NameTruncation>,
+    preset: Option<String>,
+    theme: Option<String>,
+    /// `[presets.<name>]` tables, by name
+    #[serde(default)]
+    presets: BTreeMap<String, PresetFile>,
+}
+
+/// A `[presets.<name>]` table, on top of the default layout
+#[derive(Deserialize)]
+struct PresetFile {
+    details_height: Option<u16>,
+    columns: Option<String>,
+    threads: Option<bool>,
+    theme: Option<String>,
+}
+
+impl Config {
+    /// Load the config file, falling back to defaults if it is missing or malformed
+    pub fn load() -> Self {
+        let Some(path) = config_path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        Self::parse(&contents).unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring malformed {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        let file: ConfigFile = toml::from_str(contents)?;
+        let mut config = Self::default();
+
+        set(&mut config.scrolloff, file.scrolloff);
+        set(&mut config.page_size, file.page_size);
+        set(&mut config.confirm_quit, file.confirm_quit);
+        set(
+            &mut config.kill_confirm_timeout_secs,
+            file.kill_confirm_timeout_secs,
+        );
+        set(&mut config.hide_kernel_threads, file.hide_kernel_threads);
+        if let Some(value) = &file.memory_thresholds {
+            config.memory_thresholds_mb = parse_thresholds(value);
+        }
+        set(&mut config.redact_environment, file.redact_environment);
+        set(&mut config.name_truncation, file.name_truncation);
+        config.command = file.command.filter(|c| !c.is_empty());
+        config.preset = file.preset;
+        config.presets.extend(
+            file.presets
+                .into_iter()
+                .filter(|(name, _)| name != "default")
+                .map(|(name, preset)| parse_preset(name, preset)),
+        );
+        config.theme = file.theme;
+        for preset in &mut config.presets {
+            if preset.theme.is_none() {
+                preset.theme.clone_from(&config.theme);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Choices made inside the app that carry over to the next run. They live in `state.toml`
+/// next to the config file, so saving them never rewrites the hand-edited `config.toml`.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub sort_column: SortColumn,
+    pub sort_ascending: bool,
+    pub refresh_interval_ms: u64,
+    /// Columns picked in the `c` menu, `None` leaves them to the layout preset
+    pub columns: Option<Vec<Column>>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            sort_column: SortColumn::Memory,
+            sort_ascending: false,
+            refresh_interval_ms: DEFAULT_REFRESH_INTERVAL.as_millis() as u64,
+            columns: None,
+        }
+    }
+}
+
+impl Preferences {
+    /// Load the saved preferences, falling back to defaults if they are missing or malformed
+    pub fn load() -> Self {
+        let Some(path) = state_path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring malformed {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = state_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(
+            path,
+            format!("# Saved by ratatui_learn on quit\n{contents}"),
+        )
+    }
+}
+
+/// A `[presets.<name>]` table, starting from the default layout
+fn parse_preset(name: String, file: PresetFile) -> LayoutPreset {
+    let mut preset = LayoutPreset::default();
+    set(&mut preset.details_height, file.details_height);
+    set(&mut preset.threads, file.threads);
+    preset.theme = file.theme;
+    if let Some(columns) = &file.columns {
+        preset.columns = parse_columns(columns, &format!("preset {name}"));
+    }
+    preset.name = name;
+    preset
+}
+
+/// A comma separated list of column names, warning about (and skipping) unknown ones
+fn parse_columns(value: &str, source: &str) -> Vec<Column> {
+    value
+        .split(',')
+        .filter_map(|column| {
+            let parsed = Column::from_name(column);
+            if parsed.is_none() {
+                eprintln!("Warning: unknown column '{column}' in {source}");
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// `"100,500,1024"`, skipping anything that isn't a positive number of MB
+fn parse_thresholds(value: &str) -> Vec<f64> {
+    let mut thresholds: Vec<f64> = value
+        .split(',')
+        .filter_map(|threshold| match threshold.trim().parse::<f64>() {
+            Ok(mb) if mb > 0.0 => Some(mb),
+            _ => {
+                eprintln!("Warning: invalid memory threshold '{threshold}'");
+                None
+            }
+        })
+        .collect();
+    thresholds.sort_by(f64::total_cmp);
+    thresholds.dedup();
+    thresholds
+}
+
+/// Overwrite `field` with `value` if the file set it, keeping the default otherwise
+fn set<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
+
+/// `config.toml` in the platform config directory, `$XDG_CONFIG_HOME/ratatui_learn` on Linux
+pub fn config_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "ratatui_learn").map(|dirs| dirs.config_dir().join("config.toml"))
+}
+
+/// `state.toml` next to `config.toml`
+fn state_path() -> Option<PathBuf> {
+    config_path().map(|path| path.with_file_name("state.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `config.toml` example from the README
+    fn readme_example() -> &'static str {
+        let readme = include_str!("../README.md");
+        let start = readme.find("```toml\n").expect("README has a toml example") + 8;
+        let end = start + readme[start..].find("```").expect("the example is closed");
+        &readme[start..end]
+    }
+
+    #[test]
+    fn commands_keep_their_inner_quotes() {
+        let command = |line: &str| Config::parse(line).unwrap().command;
+        assert_eq!(
+            command("command = 'strace -p {pid}' # literal"),
+            Some("strace -p {pid}".to_string())
+        );
+        assert_eq!(
+            command(r#"command = "grep \"x # y\" {name}""#),
+            Some(r#"grep "x # y" {name}"#.to_string())
+        );
+        assert!(Config::parse("confirm_quit = \"sometimes\"").is_err());
+    }
+
+    #[test]
+    fn readme_example_parses() {
+        let config = Config::parse(readme_example()).expect("the README example is valid");
+        assert_eq!(config.scrolloff, 3);
+        assert_eq!(config.page_size, 25);
+        assert_eq!(config.command.as_deref(), Some("strace -p {pid}"));
+        assert_eq!(config.preset.as_deref(), Some("triage"));
+
+        let preset = |name: &str| {
+            config
+                .presets
+                .iter()
+                .find(|preset| preset.name == name)
+                .unwrap_or_else(|| panic!("preset {name} is missing"))
+        };
+        let triage = preset("triage");
+        assert_eq!(triage.details_height, 0);
+        assert_eq!(triage.columns.len(), 8);
+        assert_eq!(triage.theme.as_deref(), Some("high-contrast"));
+
+        let deep_dive = preset("deep-dive");
+        assert_eq!(deep_dive.details_height, 20);
+        assert!(deep_dive.threads);
+        assert_eq!(deep_dive.theme.as_deref(), Some("default"));
+    }
+}
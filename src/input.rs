@@ -0,0 +1,139 @@
+use std::{
+    io,
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+use ratatui::crossterm::event::{self, Event};
+
+/// How long the thread waits for an event before checking whether it should stop or pause
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Reads terminal events on its own thread, so the main loop can sleep until either an
+/// event arrives or the next refresh is due
+pub struct InputThread {
+    events: Receiver<io::Result<Event>>,
+    gate: Arc<Gate>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// How `pause` keeps the thread off stdin. The thread checks `paused` between polls, never
+/// holding a lock while it polls, and says it has stopped through `parked`.
+struct Gate {
+    paused: AtomicBool,
+    parked: Mutex<bool>,
+    changed: Condvar,
+}
+
+impl Gate {
+    fn parked(&self) -> MutexGuard<'_, bool> {
+        self.parked.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Returned by `pause`, the thread reads events again once it's dropped
+pub struct Paused<'a> {
+    gate: &'a Gate,
+}
+
+impl Drop for Paused<'_> {
+    fn drop(&mut self) {
+        // Under the lock, so the thread can't miss the wakeup between its check and its wait
+        let _parked = self.gate.parked();
+        self.gate.paused.store(false, Ordering::SeqCst);
+        self.gate.changed.notify_all();
+    }
+}
+
+impl InputThread {
+    pub fn spawn() -> Self {
+        let (sender, events) = mpsc::channel();
+        let gate = Arc::new(Gate {
+            paused: AtomicBool::new(false),
+            parked: Mutex::new(false),
+            changed: Condvar::new(),
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let gate = Arc::clone(&gate);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    if gate.paused.load(Ordering::SeqCst) {
+                        let mut parked = gate.parked();
+                        *parked = true;
+                        gate.changed.notify_all();
+                        while gate.paused.load(Ordering::SeqCst) && !stop.load(Ordering::SeqCst) {
+                            parked = gate.changed.wait(parked).unwrap_or_else(|e| e.into_inner());
+                        }
+                        *parked = false;
+                        continue;
+                    }
+
+                    let event = match event::poll(POLL_INTERVAL) {
+                        Ok(true) => event::read(),
+                        Ok(false) => continue,
+                        Err(e) => Err(e),
+                    };
+                    // The receiver is gone once the app has quit
+                    if sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+
+        Self {
+            events,
+            gate,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// The next event, or `None` if nothing happened within `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<Event>> {
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(event?)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => bail!("Input thread stopped"),
+        }
+    }
+
+    /// Stop reading events until the guard is dropped, e.g. while a child process owns stdin.
+    /// Only returns once the thread has finished its current poll and parked.
+    pub fn pause(&self) -> Paused<'_> {
+        self.gate.paused.store(true, Ordering::SeqCst);
+        let mut parked = self.gate.parked();
+        while !*parked && !self.handle.as_ref().is_none_or(JoinHandle::is_finished) {
+            // Timed, so a thread that exited instead of parking can't leave this waiting forever
+            (parked, _) = self
+                .gate
+                .changed
+                .wait_timeout(parked, POLL_INTERVAL)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        Paused { gate: &self.gate }
+    }
+}
+
+impl Drop for InputThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        {
+            let _parked = self.gate.parked();
+            self.gate.changed.notify_all();
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
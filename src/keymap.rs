@@ -0,0 +1,401 @@
+use std::{collections::BTreeMap, fs};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::config_path;
+
+/// Everything a key can do in the process table
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Action {
+    Quit,
+    Next,
+    Previous,
+    First,
+    Last,
+    HalfPageDown,
+    HalfPageUp,
+    NextPage,
+    PreviousPage,
+    ScrollDetailsDown,
+    ScrollDetailsUp,
+    Kill,
+    Mark,
+    Pin,
+    #[cfg(unix)]
+    Renice,
+    #[cfg(unix)]
+    Freeze,
+    Command,
+    Help,
+    Columns,
+    Search,
+    SortPid,
+    SortName,
+    SortMemory,
+    SortCpu,
+    SortFds,
+    SortThreads,
+    SortDiskIo,
+    SortUptime,
+    FdColumn,
+    ThreadsColumn,
+    DiskColumns,
+    ThreadList,
+    Environment,
+    StatusFilter,
+    MemoryFilter,
+    KernelThreads,
+    MyProcesses,
+    Layout,
+    Theme,
+    PerCoreCpu,
+    DetailsLayout,
+    Tree,
+    Fold,
+    ExportJson,
+    ExportCsv,
+    OpenFolder,
+    SlowerRefresh,
+    FasterRefresh,
+    SetInterval,
+    SnapshotGone,
+    RunCommand,
+    Log,
+    Debug,
+}
+
+/// Every action with its default keys, in the syntax `keymap.toml` uses
+const DEFAULTS: &[(Action, &str)] = &[
+    (Action::Quit, "q"),
+    (Action::Next, "Down j"),
+    (Action::Previous, "Up k"),
+    (Action::First, "g"),
+    (Action::Last, "G"),
+    (Action::HalfPageDown, "Ctrl-d"),
+    (Action::HalfPageUp, "Ctrl-u"),
+    (Action::NextPage, "]"),
+    (Action::PreviousPage, "["),
+    (Action::ScrollDetailsDown, "PageDown"),
+    (Action::ScrollDetailsUp, "PageUp"),
+    (Action::Kill, "x"),
+    (Action::Mark, "Space"),
+    (Action::Pin, "P"),
+    #[cfg(unix)]
+    (Action::Renice, "r"),
+    #[cfg(unix)]
+    (Action::Freeze, "z"),
+    (Action::Command, ":"),
+    (Action::Help, "?"),
+    (Action::Columns, "c"),
+    (Action::Search, "/"),
+    (Action::SortPid, "p"),
+    (Action::SortName, "n"),
+    (Action::SortMemory, "m"),
+    (Action::SortCpu, "u"),
+    (Action::SortFds, "f"),
+    (Action::SortThreads, "h"),
+    (Action::SortDiskIo, "i"),
+    (Action::SortUptime, "a"),
+    (Action::FdColumn, "F"),
+    (Action::ThreadsColumn, "#"),
+    (Action::DiskColumns, "I"),
+    (Action::ThreadList, "H"),
+    (Action::Environment, "v"),
+    (Action::StatusFilter, "s"),
+    (Action::MemoryFilter, "M"),
+    (Action::KernelThreads, "K"),
+    (Action::MyProcesses, "U"),
+    (Action::Layout, "L"),
+    (Action::Theme, "T"),
+    (Action::PerCoreCpu, "C"),
+    (Action::DetailsLayout, "D"),
+    (Action::Tree, "t"),
+    (Action::Fold, "Enter"),
+    (Action::ExportJson, "e"),
+    (Action::ExportCsv, "E"),
+    (Action::OpenFolder, "o"),
+    (Action::SlowerRefresh, "+"),
+    (Action::FasterRefresh, "-"),
+    (Action::SetInterval, "="),
+    (Action::SnapshotGone, "d"),
+    (Action::RunCommand, "!"),
+    (Action::Log, "l"),
+    (Action::Debug, "F12"),
+];
+
+impl Action {
+    /// How the action is named in `keymap.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Next => "next",
+            Action::Previous => "previous",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::HalfPageDown => "half_page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::NextPage => "next_page",
+            Action::PreviousPage => "previous_page",
+            Action::ScrollDetailsDown => "scroll_details_down",
+            Action::ScrollDetailsUp => "scroll_details_up",
+            Action::Kill => "kill",
+            Action::Mark => "mark",
+            Action::Pin => "pin",
+            #[cfg(unix)]
+            Action::Renice => "renice",
+            #[cfg(unix)]
+            Action::Freeze => "freeze",
+            Action::Command => "command",
+            Action::Help => "help",
+            Action::Columns => "columns",
+            Action::Search => "search",
+            Action::SortPid => "sort_pid",
+            Action::SortName => "sort_name",
+            Action::SortMemory => "sort_memory",
+            Action::SortCpu => "sort_cpu",
+            Action::SortFds => "sort_fds",
+            Action::SortThreads => "sort_threads",
+            Action::SortDiskIo => "sort_disk_io",
+            Action::SortUptime => "sort_uptime",
+            Action::FdColumn => "fd_column",
+            Action::ThreadsColumn => "threads_column",
+            Action::DiskColumns => "disk_columns",
+            Action::ThreadList => "thread_list",
+            Action::Environment => "environment",
+            Action::StatusFilter => "status_filter",
+            Action::MemoryFilter => "memory_filter",
+            Action::KernelThreads => "kernel_threads",
+            Action::MyProcesses => "my_processes",
+            Action::Layout => "layout",
+            Action::Theme => "theme",
+            Action::PerCoreCpu => "per_core_cpu",
+            Action::DetailsLayout => "details_layout",
+            Action::Tree => "tree",
+            Action::Fold => "fold",
+            Action::ExportJson => "export_json",
+            Action::ExportCsv => "export_csv",
+            Action::OpenFolder => "open_folder",
+            Action::SlowerRefresh => "slower_refresh",
+            Action::FasterRefresh => "faster_refresh",
+            Action::SetInterval => "set_interval",
+            Action::SnapshotGone => "snapshot_gone",
+            Action::RunCommand => "run_command",
+            Action::Log => "log",
+            Action::Debug => "debug",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        DEFAULTS
+            .iter()
+            .map(|&(action, _)| action)
+            .find(|action| action.name() == name.trim())
+    }
+}
+
+/// A key as bindings see it. Shift is already part of the character, so only Ctrl counts.
+#[derive(PartialEq, Eq, Copy, Clone)]
+struct Key {
+    code: KeyCode,
+    ctrl: bool,
+}
+
+impl Key {
+    /// `j`, `G`, `Space`, `Enter`, `PageDown`, `F12`, `Ctrl-d` and so on
+    fn parse(name: &str) -> Option<Self> {
+        let (ctrl, name) = match name
+            .strip_prefix("Ctrl-")
+            .or_else(|| name.strip_prefix("Ctrl+"))
+        {
+            Some(rest) => (true, rest),
+            None => (false, name),
+        };
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name {
+                "Space" => KeyCode::Char(' '),
+                "Enter" => KeyCode::Enter,
+                "Tab" => KeyCode::Tab,
+                "Esc" => KeyCode::Esc,
+                "Backspace" => KeyCode::Backspace,
+                "Delete" => KeyCode::Delete,
+                "Up" => KeyCode::Up,
+                "Down" => KeyCode::Down,
+                "Left" => KeyCode::Left,
+                "Right" => KeyCode::Right,
+                "Home" => KeyCode::Home,
+                "End" => KeyCode::End,
+                "PageUp" => KeyCode::PageUp,
+                "PageDown" => KeyCode::PageDown,
+                _ => KeyCode::F(name.strip_prefix('F')?.parse().ok()?),
+            },
+        };
+
+        Some(Self { code, ctrl })
+    }
+
+    /// How the help shows the key, close to the `parse` syntax but with arrows for arrow keys
+    fn label(self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Delete => "Delete".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{n}"),
+            code => format!("{code:?}"),
+        };
+        if self.ctrl {
+            format!("Ctrl-{name}")
+        } else {
+            name
+        }
+    }
+
+    fn from_event(event: &KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+        }
+    }
+}
+
+/// Which action each key runs in the process table. Keys stay in the order they were listed,
+/// so the help shows them the way the defaults or `keymap.toml` wrote them.
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULTS
+            .iter()
+            .flat_map(|&(action, keys)| {
+                keys.split_whitespace()
+                    .filter_map(Key::parse)
+                    .map(move |key| (key, action))
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The defaults with `keymap.toml` (next to `config.toml`) applied over them
+    pub fn load() -> Self {
+        let Some(path) = config_path().map(|path| path.with_file_name("keymap.toml")) else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// `action = "key key ..."` lines. An action listed here loses its default keys, and a
+    /// key taken here is dropped from whatever had it by default.
+    fn parse(contents: &str) -> Self {
+        // Sorted, so conflicts are always settled the same way
+        let entries: BTreeMap<String, String> = match toml::from_str(contents) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Warning: ignoring malformed keymap.toml: {e}");
+                return Self::default();
+            }
+        };
+
+        let mut custom: Vec<(Key, Action)> = Vec::new();
+        let mut rebound = Vec::new();
+        for (name, keys) in &entries {
+            let Some(action) = Action::from_name(name) else {
+                eprintln!("Warning: unknown action '{name}' in keymap.toml");
+                continue;
+            };
+            rebound.push(action);
+
+            for key_name in keys.split_whitespace() {
+                let Some(key) = Key::parse(key_name) else {
+                    eprintln!("Warning: unknown key '{key_name}' for {name} in keymap.toml");
+                    continue;
+                };
+                match custom.iter().find(|(bound, _)| *bound == key) {
+                    Some((_, other)) if *other != action => eprintln!(
+                        "Warning: '{key_name}' is bound to both {} and {name} in keymap.toml, \
+                         keeping {}",
+                        other.name(),
+                        other.name()
+                    ),
+                    Some(_) => {}
+                    None => custom.push((key, action)),
+                }
+            }
+        }
+
+        let mut keymap = Self::default();
+        keymap.bindings.retain(|(key, action)| {
+            !rebound.contains(action) && !custom.iter().any(|(bound, _)| bound == key)
+        });
+        for &(action, _) in DEFAULTS {
+            let had_keys = keymap.bindings.iter().any(|&(_, a)| a == action);
+            if !had_keys && !rebound.contains(&action) {
+                eprintln!(
+                    "Warning: keymap.toml took every key of {}, it can't be used",
+                    action.name()
+                );
+            }
+        }
+        keymap.bindings.extend(custom);
+
+        keymap
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|&(_, action)| action)
+    }
+
+    /// The keys that run `action`, as the help shows them. Empty if `keymap.toml` took them all.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, bound)| bound == action)
+            .map(|(key, _)| key.label())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_labels_follow_the_keymap() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.keys(Action::Next), ["↓", "j"]);
+        assert_eq!(keymap.keys(Action::HalfPageDown), ["Ctrl-d"]);
+        assert_eq!(keymap.keys(Action::Mark), ["Space"]);
+
+        let keymap = Keymap::parse("kill = \"X k\"\nnext = \"j PageDown\"\n");
+        assert_eq!(keymap.keys(Action::Kill), ["X", "k"]);
+        assert_eq!(keymap.keys(Action::Next), ["j", "PgDn"]);
+        // Keys taken from other actions drop out of their labels, even the only one
+        assert_eq!(keymap.keys(Action::Previous), ["↑"]);
+        assert!(keymap.keys(Action::ScrollDetailsDown).is_empty());
+    }
+}
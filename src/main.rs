@@ -1,38 +1,77 @@
 mod app;
+mod config;
+mod input;
+mod keymap;
+mod snapshot;
 mod system_data;
+mod terminal;
+mod theme;
 mod ui;
 mod utils;
 
 use crate::app::App;
+use crate::config::{Config, Preferences};
+use crate::keymap::Keymap;
+use crate::snapshot::Snapshot;
 use crate::ui::run_app;
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use ratatui::Terminal;
 
 use ratatui::backend::CrosstermBackend;
-use ratatui::crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
-};
-use std::io;
+use std::{io, path::PathBuf};
+
+/// Command line options
+#[derive(Default)]
+struct Args {
+    /// Snapshot file to diff the live process list against
+    compare: Option<PathBuf>,
+    /// Go through the motions of killing, renicing and freezing without doing any of it
+    dry_run: bool,
+}
+
+impl Args {
+    fn parse() -> Result<Self> {
+        let mut args = Args::default();
+        let mut raw = std::env::args().skip(1);
+
+        while let Some(arg) = raw.next() {
+            match arg.as_str() {
+                "--compare" => {
+                    let path = raw.next().context("--compare needs a snapshot file")?;
+                    args.compare = Some(PathBuf::from(path));
+                }
+                "--dry-run" => args.dry_run = true,
+                _ => bail!(
+                    "Unknown argument '{arg}'\nUsage: ratatui_learn [--compare <snapshot.csv|snapshot.json>] [--dry-run]"
+                ),
+            }
+        }
+
+        Ok(args)
+    }
+}
 
 fn main() -> Result<()> {
-    enable_raw_mode().context("Failed to enable raw mode")?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?; // Enter a new screen and enable mouse control
-    let backend = CrosstermBackend::new(stdout);
+    let args = Args::parse()?;
+    let config = Config::load();
+    let preferences = Preferences::load();
+    let keymap = Keymap::load();
+    // Load before touching the terminal so a bad file reports a normal error
+    let snapshot = args.compare.as_deref().map(Snapshot::load).transpose()?;
+
+    terminal::enter()?; // Enter a new screen and enable mouse control
+    terminal::restore_on_panic();
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?; // Initialize the terminal
 
-    let app = App::new();
-    let res = run_app(&mut terminal, app); // Main app logic
+    let mut app = App::new(&config, preferences);
+    app.dry_run = args.dry_run;
+    if let Some(snapshot) = snapshot {
+        app.set_snapshot(snapshot);
+    }
+    let res = run_app(&mut terminal, app, &keymap); // Main app logic
 
-    disable_raw_mode()?;
-    execute!(
-        // Close the new window
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    terminal::leave()?; // Close the new window
     terminal.show_cursor()?;
 
     // Handle potential errors
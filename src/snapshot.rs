@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use sysinfo::Pid;
+
+use crate::system_data::{ProcessInfo, ProcessRecord};
+
+/// Memory growth (or shrinkage) below this many MB is noise, not a change
+const MIN_MEMORY_CHANGE_MB: f64 = 10.0;
+/// ...and so is anything below this fraction of the snapshot value
+const MIN_MEMORY_CHANGE_RATIO: f64 = 0.2;
+
+/// One process as it was recorded in a snapshot file
+#[derive(Debug)]
+pub struct SnapshotEntry {
+    pub pid: Pid,
+    pub name: String,
+    /// Older snapshots may not have recorded memory
+    pub memory_mb: Option<f64>,
+}
+
+/// A previously saved process list to compare the live system against
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// What differs between a snapshot and the live process list
+#[derive(Default)]
+pub struct SnapshotDiff {
+    /// PIDs of live processes that weren't in the snapshot
+    pub new: HashSet<Pid>,
+    /// Memory change in MB for live processes that grew or shrank noticeably
+    pub changed: HashMap<Pid, f64>,
+    /// Indices into `Snapshot::entries` of processes that no longer exist
+    pub gone: Vec<usize>,
+}
+
+impl Snapshot {
+    /// Read a JSON or CSV snapshot, as exported with `e` or `E`
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+
+        let entries = if contents.trim_start().starts_with('[') {
+            load_json(&contents).with_context(|| {
+                format!("Snapshot {} isn't a usable JSON snapshot", path.display())
+            })?
+        } else {
+            load_csv(&contents).with_context(|| {
+                format!("Snapshot {} isn't a usable CSV snapshot", path.display())
+            })?
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// A process is the same one if both its PID and name match, so reused PIDs count as new
+    pub fn compare(&self, processes: &[ProcessInfo]) -> SnapshotDiff {
+        let recorded: HashMap<(Pid, &str), &SnapshotEntry> = self
+            .entries
+            .iter()
+            .map(|entry| ((entry.pid, entry.name.as_str()), entry))
+            .collect();
+        let mut diff = SnapshotDiff::default();
+
+        for process in processes {
+            match recorded.get(&(process.pid, process.name.as_str())) {
+                None => {
+                    diff.new.insert(process.pid);
+                }
+                Some(entry) => {
+                    if let Some(before) = entry.memory_mb {
+                        let delta = process.memory_mb - before;
+                        if delta.abs() >= MIN_MEMORY_CHANGE_MB
+                            && delta.abs() >= before * MIN_MEMORY_CHANGE_RATIO
+                        {
+                            diff.changed.insert(process.pid, delta);
+                        }
+                    }
+                }
+            }
+        }
+
+        let live: HashSet<(Pid, &str)> =
+            processes.iter().map(|p| (p.pid, p.name.as_str())).collect();
+        diff.gone = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !live.contains(&(entry.pid, entry.name.as_str())))
+            .map(|(i, _)| i)
+            .collect();
+
+        diff
+    }
+}
+
+/// A JSON array of `ProcessRecord`s
+fn load_json(contents: &str) -> Result<Vec<SnapshotEntry>> {
+    let records: Vec<ProcessRecord> = serde_json::from_str(contents)?;
+    Ok(records
+        .into_iter()
+        .map(|record| SnapshotEntry {
+            pid: Pid::from_u32(record.pid),
+            name: record.name,
+            memory_mb: record.memory_mb,
+        })
+        .collect())
+}
+
+/// Columns are found by header name, so extra or reordered columns from other versions are
+/// fine as long as `pid` and `name` are present
+fn load_csv(contents: &str) -> Result<Vec<SnapshotEntry>> {
+    let mut records = parse_csv(contents).into_iter();
+    let Some(header) = records.next() else {
+        bail!("There is no header row");
+    };
+
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(pid_col), Some(name_col)) = (column("pid"), column("name")) else {
+        bail!("The header needs at least `pid` and `name` columns");
+    };
+    let memory_col = column("memory_mb");
+
+    let entries = records
+        .filter_map(|fields| {
+            Some(SnapshotEntry {
+                pid: fields.get(pid_col)?.trim().parse().ok()?,
+                name: fields.get(name_col)?.clone(),
+                memory_mb: memory_col
+                    .and_then(|col| fields.get(col))
+                    .and_then(|value| value.parse().ok()),
+            })
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Split CSV into records of fields, honouring double-quoted fields with `""` escapes.
+/// Line breaks inside quotes belong to the field, blank lines between records are skipped.
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                let record = std::mem::take(&mut fields);
+                if record.iter().any(|field| !field.trim().is_empty()) {
+                    records.push(record);
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    if fields.iter().any(|field| !field.trim().is_empty()) {
+        records.push(fields);
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::app::Column;
+    use crate::system_data::{export_snapshot, export_snapshot_csv};
+
+    #[test]
+    fn awkward_names_survive_a_csv_round_trip() {
+        let names = ["weird,\"name", "two\nlines", "plain"];
+        let processes: Vec<ProcessInfo> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ProcessInfo::sample(100 + i as u32, name, 12.5))
+            .collect();
+        let borrowed: Vec<&ProcessInfo> = processes.iter().collect();
+        let path = std::env::temp_dir().join(format!("ratatui_learn-{}.csv", std::process::id()));
+
+        export_snapshot_csv(
+            &borrowed,
+            &[Column::Pid, Column::Name, Column::Memory],
+            Duration::from_secs(2),
+            &path,
+        )
+        .unwrap();
+        let snapshot = Snapshot::load(&path);
+        let _ = fs::remove_file(&path);
+        let snapshot = snapshot.unwrap();
+
+        let loaded: Vec<(u32, &str, Option<f64>)> = snapshot
+            .entries
+            .iter()
+            .map(|entry| (entry.pid.as_u32(), entry.name.as_str(), entry.memory_mb))
+            .collect();
+        assert_eq!(
+            loaded,
+            [
+                (100, "weird,\"name", Some(12.5)),
+                (101, "two\nlines", Some(12.5)),
+                (102, "plain", Some(12.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_snapshots_load_back() {
+        let processes = [
+            ProcessInfo::sample(1, "init", 4.0),
+            ProcessInfo::sample(2, "weird,\"name", 8.5),
+        ];
+        let borrowed: Vec<&ProcessInfo> = processes.iter().collect();
+        let path = std::env::temp_dir().join(format!(
+            "ratatui_learn-{}-snapshot-load.json",
+            std::process::id()
+        ));
+
+        export_snapshot(&borrowed, &path).unwrap();
+        let snapshot = Snapshot::load(&path);
+        let _ = fs::remove_file(&path);
+        let snapshot = snapshot.unwrap();
+
+        let loaded: Vec<(u32, &str, Option<f64>)> = snapshot
+            .entries
+            .iter()
+            .map(|entry| (entry.pid.as_u32(), entry.name.as_str(), entry.memory_mb))
+            .collect();
+        assert_eq!(
+            loaded,
+            [(1, "init", Some(4.0)), (2, "weird,\"name", Some(8.5))]
+        );
+    }
+}
@@ -1,15 +1,142 @@
-use sysinfo::System;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessStatus, System, Users};
+
+use crate::app::Column;
+use crate::utils::csv_field;
+
+/// Open file descriptors are only counted where `/proc/<pid>/fd` exists
+pub const FD_COUNT_SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Per-thread details come from `/proc/<pid>/task`, which only Linux has
+pub const THREADS_SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Network counters are read per network namespace, which only Linux exposes per process
+pub const NET_SUPPORTED: bool = cfg!(all(feature = "net", target_os = "linux"));
+
+/// Environment variables come from `/proc/<pid>/environ`, which only Linux has
+pub const ENVIRONMENT_SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Variable names containing any of these (in any case) are treated as secrets
+const SECRET_MARKERS: [&str; 5] = ["TOKEN", "KEY", "PASSWORD", "SECRET", "CREDENTIAL"];
+
+/// Kernel clock ticks per second (`USER_HZ`), the unit of the CPU times in `/proc`. Asked for
+/// once, 100 if the kernel won't say, which is what every mainstream Linux build uses anyway.
+#[cfg(target_os = "linux")]
+fn clock_ticks() -> f32 {
+    static CLOCK_TICKS: std::sync::OnceLock<f32> = std::sync::OnceLock::new();
+    *CLOCK_TICKS.get_or_init(|| {
+        // SAFETY: sysconf only reads its argument
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 { ticks as f32 } else { 100.0 }
+    })
+}
 
 #[derive(Debug)]
 pub struct ProcessInfo {
-    /// This was a stupid move, change it later
-    pub pid: String,
+    pub pid: Pid,
     pub name: String,
+    /// Full argv, empty for kernel threads
+    pub cmd: Vec<String>,
+    /// `None` for kernel threads and where we may not look
+    pub exe_path: Option<PathBuf>,
     pub memory_mb: f64,
+    /// Percent of one core, so busy multithreaded processes can go past 100
+    pub cpu_usage: f32,
+    /// `None` when not requested, unsupported, or not permitted
+    pub fd_count: Option<u32>,
+    /// Number of threads, `None` where the platform doesn't say (and for threads themselves)
+    pub threads: Option<usize>,
+    /// Bytes read from disk since the previous refresh, 0 the first time a process is seen
+    pub disk_read_bytes: u64,
+    /// Bytes written to disk since the previous refresh, 0 the first time a process is seen
+    pub disk_written_bytes: u64,
+    pub status: ProcessStatus,
+    /// Seconds since the epoch, used to tell a reused PID apart from the original process
+    pub start_time: u64,
+    pub parent: Option<Pid>,
+    /// Name of the owning user, `None` where the platform or permissions don't tell
+    pub user: Option<String>,
+}
+
+/// Whole-machine totals for the summary bar
+#[derive(Debug, Default)]
+pub struct SystemSummary {
+    pub used_mem_mb: f64,
+    pub total_mem_mb: f64,
+    /// Average over all cores, so this one tops out at 100
+    pub cpu_percent: f32,
+    /// Usage of each logical core in the order the OS numbers them
+    pub per_core: Vec<f32>,
+}
+
+/// One line of the process tree
+#[derive(Debug)]
+pub struct TreeRow {
+    /// Index into the process list
+    pub index: usize,
+    /// Box-drawing connectors that go in front of the name
+    pub prefix: String,
+    pub has_children: bool,
+    pub collapsed: bool,
 }
 
-pub fn get_system_processes() -> Vec<ProcessInfo> {
-    let mut system = System::new_all();
+#[derive(Debug)]
+pub struct ThreadInfo {
+    pub tid: u32,
+    pub name: String,
+    /// Single letter state from `/proc`, e.g. `R`, `S` or `D`
+    pub state: char,
+    /// User + system time in clock ticks
+    pub cpu_ticks: u64,
+    /// Filled in by the caller from the difference between two samples
+    pub cpu_usage: f32,
+}
+
+/// Bytes moved through every interface but loopback, as seen from one process
+#[derive(Debug, Clone, Copy)]
+pub struct NetCounters {
+    pub received: u64,
+    pub transmitted: u64,
+}
+
+/// The single letter `ps` shows for a status (`R`, `S`, `D`, `Z`, ...)
+pub fn state_code(status: ProcessStatus) -> char {
+    match status {
+        ProcessStatus::Run => 'R',
+        ProcessStatus::Sleep => 'S',
+        ProcessStatus::UninterruptibleDiskSleep => 'D',
+        ProcessStatus::Zombie => 'Z',
+        ProcessStatus::Stop => 'T',
+        ProcessStatus::Tracing => 't',
+        ProcessStatus::Dead => 'X',
+        ProcessStatus::Idle => 'I',
+        ProcessStatus::Wakekill => 'K',
+        ProcessStatus::Waking => 'W',
+        ProcessStatus::Parked => 'P',
+        _ => '?',
+    }
+}
+
+/// Processes in `D` state are waiting on I/O and won't even react to SIGKILL until it completes
+pub fn is_blocked_on_io(status: ProcessStatus) -> bool {
+    status == ProcessStatus::UninterruptibleDiskSleep
+}
+
+/// `system` must be kept between calls, CPU usage is measured from one refresh to the next.
+/// `count_fds` walks `/proc` once per process, so only ask for it when the column is visible.
+pub fn get_system_processes(
+    system: &mut System,
+    users: &Users,
+    count_fds: bool,
+) -> Vec<ProcessInfo> {
     system.refresh_all();
 
     system
@@ -17,12 +144,510 @@ pub fn get_system_processes() -> Vec<ProcessInfo> {
         .iter()
         .map(|(pid, process)| {
             let memory_mb = process.memory() as f64 / 1024.0 / 1024.0;
+            let disk = process.disk_usage();
 
             ProcessInfo {
-                pid: pid.to_string(),
+                pid: *pid,
                 name: process.name().to_string_lossy().to_string(),
-                memory_mb
+                cmd: process
+                    .cmd()
+                    .iter()
+                    .map(|arg| arg.to_string_lossy().to_string())
+                    .collect(),
+                exe_path: process.exe().map(Path::to_path_buf),
+                memory_mb,
+                cpu_usage: process.cpu_usage(),
+                fd_count: count_fds.then(|| count_open_fds(*pid)).flatten(),
+                threads: process.tasks().map(|tasks| tasks.len()),
+                disk_read_bytes: disk.read_bytes,
+                disk_written_bytes: disk.written_bytes,
+                status: process.status(),
+                start_time: process.start_time(),
+                parent: process.parent(),
+                user: process
+                    .user_id()
+                    .and_then(|uid| users.get_user_by_id(uid))
+                    .map(|user| user.name().to_string()),
             }
         })
         .collect()
 }
+
+impl ProcessInfo {
+    /// Bytes read and written since the previous refresh, what the disk I/O sort goes by
+    pub fn disk_io_bytes(&self) -> u64 {
+        self.disk_read_bytes + self.disk_written_bytes
+    }
+
+    /// How long the process has been running, zero if the clock says it hasn't started yet
+    pub fn uptime(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Duration::from_secs(now.saturating_sub(self.start_time))
+    }
+
+    /// A sleeping process with just a PID, name and memory, for tests
+    #[cfg(test)]
+    pub fn sample(pid: u32, name: &str, memory_mb: f64) -> Self {
+        Self {
+            pid: Pid::from_u32(pid),
+            name: name.to_string(),
+            cmd: Vec::new(),
+            exe_path: None,
+            memory_mb,
+            cpu_usage: 0.0,
+            fd_count: None,
+            threads: None,
+            disk_read_bytes: 0,
+            disk_written_bytes: 0,
+            status: ProcessStatus::Sleep,
+            start_time: 0,
+            parent: None,
+            user: None,
+        }
+    }
+}
+
+/// `bytes` moved over `interval` as bytes per second, for disk and network counters alike
+pub fn byte_rate(bytes: u64, interval: Duration) -> u64 {
+    let secs = interval.as_secs_f64();
+    if secs <= 0.0 {
+        return 0;
+    }
+    (bytes as f64 / secs) as u64
+}
+
+/// Who is running this program, `None` if the platform doesn't say
+pub fn current_user(system: &System, users: &Users) -> Option<String> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let uid = system.process(pid)?.user_id()?;
+    users
+        .get_user_by_id(uid)
+        .map(|user| user.name().to_string())
+}
+
+/// Lay out `order` (indices into `processes`) as a tree, children below their parent in the
+/// same relative order. Processes whose parent isn't in `order` become roots, and the
+/// descendants of anything in `collapsed` are left out.
+pub fn build_tree(
+    processes: &[ProcessInfo],
+    order: &[usize],
+    collapsed: &HashSet<Pid>,
+) -> Vec<TreeRow> {
+    let present: HashSet<Pid> = order.iter().map(|&i| processes[i].pid).collect();
+    let mut children: HashMap<Pid, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for &i in order {
+        let process = &processes[i];
+        match process
+            .parent
+            .filter(|parent| *parent != process.pid && present.contains(parent))
+        {
+            Some(parent) => children.entry(parent).or_default().push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let mut rows = Vec::with_capacity(order.len());
+    for root in roots {
+        add_subtree(
+            processes,
+            &children,
+            collapsed,
+            root,
+            String::new(),
+            "",
+            &mut rows,
+        );
+    }
+    rows
+}
+
+/// `lead` goes in front of this process, `indent` in front of everything below it
+fn add_subtree(
+    processes: &[ProcessInfo],
+    children: &HashMap<Pid, Vec<usize>>,
+    collapsed: &HashSet<Pid>,
+    index: usize,
+    lead: String,
+    indent: &str,
+    rows: &mut Vec<TreeRow>,
+) {
+    let pid = processes[index].pid;
+    let kids = children.get(&pid);
+    let is_collapsed = collapsed.contains(&pid);
+    rows.push(TreeRow {
+        index,
+        prefix: lead,
+        has_children: kids.is_some(),
+        collapsed: is_collapsed,
+    });
+
+    if is_collapsed {
+        return;
+    }
+    for (n, &child) in kids.into_iter().flatten().enumerate() {
+        let last = n + 1 == kids.map_or(0, Vec::len);
+        let (branch, below) = if last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        add_subtree(
+            processes,
+            children,
+            collapsed,
+            child,
+            format!("{indent}{branch}"),
+            &format!("{indent}{below}"),
+            rows,
+        );
+    }
+}
+
+/// Totals from the last refresh of `system`
+pub fn get_system_summary(system: &System) -> SystemSummary {
+    SystemSummary {
+        used_mem_mb: system.used_memory() as f64 / 1024.0 / 1024.0,
+        total_mem_mb: system.total_memory() as f64 / 1024.0 / 1024.0,
+        cpu_percent: system.global_cpu_usage(),
+        per_core: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+    }
+}
+
+/// One process in a JSON snapshot, as written by `e` and read back by `--compare`. Only
+/// `pid` and `name` are required, so files from other versions still load.
+#[derive(Serialize, Deserialize)]
+pub struct ProcessRecord {
+    pub pid: u32,
+    pub name: String,
+    #[serde(default)]
+    pub cmd: Vec<String>,
+    /// `null` if sysinfo reported something that isn't a finite number
+    #[serde(default)]
+    pub cpu_usage: Option<f32>,
+    #[serde(default)]
+    pub memory_mb: Option<f64>,
+    #[serde(default)]
+    pub fd_count: Option<u32>,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub start_time: u64,
+    #[serde(default)]
+    pub parent: Option<u32>,
+}
+
+impl From<&ProcessInfo> for ProcessRecord {
+    fn from(process: &ProcessInfo) -> Self {
+        Self {
+            pid: process.pid.as_u32(),
+            name: process.name.clone(),
+            cmd: process.cmd.clone(),
+            // Rounded like the table shows them, the extra digits are only noise
+            cpu_usage: Some((process.cpu_usage * 10.0).round() / 10.0)
+                .filter(|cpu| cpu.is_finite()),
+            memory_mb: Some((process.memory_mb * 100.0).round() / 100.0)
+                .filter(|mb| mb.is_finite()),
+            fd_count: process.fd_count,
+            status: process.status.to_string(),
+            start_time: process.start_time,
+            parent: process.parent.map(|pid| pid.as_u32()),
+        }
+    }
+}
+
+/// Write `processes` as a JSON array, one object per process
+pub fn export_snapshot(processes: &[&ProcessInfo], path: &Path) -> Result<()> {
+    let records = processes
+        .iter()
+        .map(|&process| serde_json::to_string(&ProcessRecord::from(process)))
+        .collect::<serde_json::Result<Vec<String>>>()?;
+    let json = format!("[\n  {}\n]\n", records.join(",\n  "));
+
+    fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Write `processes` as CSV with one column per entry of `columns`. Values are raw numbers
+/// rather than display strings, and `pid`, `name` and `memory_mb` are what `--compare` reads.
+/// `disk_interval` is the time the disk byte counts cover.
+pub fn export_snapshot_csv(
+    processes: &[&ProcessInfo],
+    columns: &[Column],
+    disk_interval: Duration,
+    path: &Path,
+) -> Result<()> {
+    let header: Vec<&str> = columns.iter().map(|&column| csv_header(column)).collect();
+    let mut csv = header.join(",");
+    csv.push('\n');
+
+    for process in processes {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|&column| csv_value(process, column, disk_interval))
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    fs::write(path, csv).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn csv_header(column: Column) -> &'static str {
+    match column {
+        Column::Pid => "pid",
+        Column::Status => "status",
+        Column::Name => "name",
+        Column::Cpu => "cpu_percent",
+        Column::Memory => "memory_mb",
+        Column::Fds => "fd_count",
+        Column::Threads => "threads",
+        Column::DiskRead => "disk_read_bytes_per_sec",
+        Column::DiskWrite => "disk_write_bytes_per_sec",
+        Column::Uptime => "uptime_secs",
+    }
+}
+
+/// Unknown counts are left empty
+fn csv_value(process: &ProcessInfo, column: Column, disk_interval: Duration) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    match column {
+        Column::Pid => process.pid.to_string(),
+        Column::Status => state_code(process.status).to_string(),
+        Column::Name => csv_field(&process.name),
+        Column::Cpu => format!("{:.1}", process.cpu_usage),
+        Column::Memory => format!("{:.2}", process.memory_mb),
+        Column::Fds => optional(process.fd_count.map(|count| count.to_string())),
+        Column::Threads => optional(process.threads.map(|count| count.to_string())),
+        Column::DiskRead => byte_rate(process.disk_read_bytes, disk_interval).to_string(),
+        Column::DiskWrite => byte_rate(process.disk_written_bytes, disk_interval).to_string(),
+        Column::Uptime => process.uptime().as_secs().to_string(),
+    }
+}
+
+/// The nice value of `pid`, -1 doubles as the answer when it can't be read
+#[cfg(unix)]
+pub fn get_priority(pid: Pid) -> i32 {
+    // SAFETY: getpriority only reads its arguments
+    unsafe { libc::getpriority(libc::PRIO_PROCESS, pid.as_u32() as libc::id_t) }
+}
+
+/// Change the nice value of `pid`, lowering it needs root
+#[cfg(unix)]
+pub fn set_priority(pid: Pid, nice: i32) -> Result<()> {
+    // SAFETY: setpriority only reads its arguments
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid.as_u32() as libc::id_t, nice) };
+    if result == 0 {
+        return Ok(());
+    }
+
+    let error = std::io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::EPERM | libc::EACCES) => anyhow::bail!("permission denied (try sudo)"),
+        _ => Err(error.into()),
+    }
+}
+
+/// Pause `pid` with SIGSTOP until `resume_process`
+#[cfg(unix)]
+pub fn suspend_process(pid: Pid) -> Result<()> {
+    signal_process(pid, libc::SIGSTOP)
+}
+
+/// Let a process stopped by `suspend_process` (or Ctrl-Z) carry on with SIGCONT
+#[cfg(unix)]
+pub fn resume_process(pid: Pid) -> Result<()> {
+    signal_process(pid, libc::SIGCONT)
+}
+
+#[cfg(unix)]
+fn signal_process(pid: Pid, signal: libc::c_int) -> Result<()> {
+    // SAFETY: kill only reads its arguments
+    let result = unsafe { libc::kill(pid.as_u32() as libc::pid_t, signal) };
+    if result == 0 {
+        return Ok(());
+    }
+
+    let error = std::io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::EPERM) => anyhow::bail!("permission denied (try sudo)"),
+        Some(libc::ESRCH) => anyhow::bail!("the process has already exited"),
+        _ => Err(error.into()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn count_open_fds(pid: Pid) -> Option<u32> {
+    // Other users' processes fail with permission denied, which just leaves the count empty
+    std::fs::read_dir(format!("/proc/{pid}/fd"))
+        .ok()
+        .map(|entries| entries.count() as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count_open_fds(_pid: Pid) -> Option<u32> {
+    None
+}
+
+/// Network totals of the namespace `pid` lives in. Linux doesn't count traffic per process,
+/// so every process sharing the namespace (usually the whole system) reports the same.
+#[cfg(all(feature = "net", target_os = "linux"))]
+pub fn get_net_counters(pid: Pid) -> Option<NetCounters> {
+    let dev = std::fs::read_to_string(format!("/proc/{pid}/net/dev")).ok()?;
+    let mut counters = NetCounters {
+        received: 0,
+        transmitted: 0,
+    };
+
+    // Two header lines, then `iface: rx_bytes <7 more rx fields> tx_bytes ...`
+    for line in dev.lines().skip(2) {
+        let Some((interface, fields)) = line.split_once(':') else {
+            continue;
+        };
+        if interface.trim() == "lo" {
+            continue;
+        }
+        let fields: Vec<&str> = fields.split_whitespace().collect();
+        counters.received += fields.first()?.parse::<u64>().ok()?;
+        counters.transmitted += fields.get(8)?.parse::<u64>().ok()?;
+    }
+
+    Some(counters)
+}
+
+#[cfg(not(all(feature = "net", target_os = "linux")))]
+pub fn get_net_counters(_pid: Pid) -> Option<NetCounters> {
+    None
+}
+
+/// Every thread of `pid`, or `None` if the process is gone or we may not look at it
+#[cfg(target_os = "linux")]
+pub fn get_threads(pid: Pid) -> Option<Vec<ThreadInfo>> {
+    let tasks = std::fs::read_dir(format!("/proc/{pid}/task")).ok()?;
+
+    let threads = tasks
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let tid = entry.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            parse_thread_stat(tid, &stat)
+        })
+        .collect();
+
+    Some(threads)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_threads(_pid: Pid) -> Option<Vec<ThreadInfo>> {
+    None
+}
+
+/// The environment `pid` was started with as `(name, value)` pairs sorted by name, or `None`
+/// if the process is gone or belongs to someone else
+#[cfg(target_os = "linux")]
+pub fn get_environment(pid: Pid) -> Option<Vec<(String, String)>> {
+    let environ = std::fs::read(format!("/proc/{pid}/environ")).ok()?;
+
+    // `NAME=value` entries separated by NUL bytes, values aren't guaranteed to be UTF-8
+    let mut variables: Vec<(String, String)> = environ
+        .split(|&byte| byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            match entry.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => (entry.to_string(), String::new()),
+            }
+        })
+        .collect();
+    variables.sort();
+
+    Some(variables)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_environment(_pid: Pid) -> Option<Vec<(String, String)>> {
+    None
+}
+
+/// Kernel threads have no command line, and are either shown as `[name]` or, on Linux,
+/// are `kthreadd` (PID 2) and its children
+pub fn is_kernel_thread(process: &ProcessInfo) -> bool {
+    if !process.cmd.is_empty() {
+        return false;
+    }
+    let bracketed = process.name.starts_with('[') && process.name.ends_with(']');
+    let kthreadd = Pid::from_u32(2);
+    bracketed
+        || cfg!(target_os = "linux")
+            && (process.pid == kthreadd || process.parent == Some(kthreadd))
+}
+
+/// Whether a variable's value is likely a password, API key or similar
+pub fn is_secret_variable(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// `stat` looks like `tid (name) state ppid ...`, and the name itself may contain spaces or parens
+#[cfg(target_os = "linux")]
+fn parse_thread_stat(tid: u32, stat: &str) -> Option<ThreadInfo> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+
+    // Fields after the name start at field 3 (state), utime and stime are fields 14 and 15
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+    let state = fields.first()?.chars().next()?;
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+
+    Some(ThreadInfo {
+        tid,
+        name,
+        state,
+        cpu_ticks: utime + stime,
+        cpu_usage: 0.0,
+    })
+}
+
+/// CPU percentage used by a thread between two samples taken `elapsed_secs` apart
+#[cfg(target_os = "linux")]
+pub fn thread_cpu_usage(previous_ticks: u64, current_ticks: u64, elapsed_secs: f32) -> f32 {
+    if elapsed_secs <= 0.0 {
+        return 0.0;
+    }
+    current_ticks.saturating_sub(previous_ticks) as f32 / clock_ticks() / elapsed_secs * 100.0
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn thread_cpu_usage(_previous_ticks: u64, _current_ticks: u64, _elapsed_secs: f32) -> f32 {
+    0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_numbers_export_as_null() {
+        let mut process = ProcessInfo::sample(7, "nan\"cpu", 1.234);
+        process.cpu_usage = f32::NAN;
+        let path = std::env::temp_dir().join(format!(
+            "ratatui_learn-{}-nan-export.json",
+            std::process::id()
+        ));
+
+        export_snapshot(&[&process], &path).unwrap();
+        let json = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+        let records: serde_json::Value = serde_json::from_str(&json.unwrap()).unwrap();
+
+        assert_eq!(records[0]["pid"], 7);
+        assert_eq!(records[0]["name"], "nan\"cpu");
+        assert!(records[0]["cpu_usage"].is_null());
+        assert_eq!(records[0]["memory_mb"], 1.23);
+    }
+}
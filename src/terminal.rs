@@ -0,0 +1,32 @@
+use anyhow::{Context, Result};
+use ratatui::crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use std::io;
+
+/// Switch to raw mode on a new screen with mouse control
+pub fn enter() -> Result<()> {
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    Ok(())
+}
+
+/// Restore the terminal before a panic message prints, otherwise it lands on the
+/// alternate screen and the shell is left in raw mode. The previous hook still runs
+/// afterwards so the message and backtrace look as usual.
+pub fn restore_on_panic() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = leave();
+        previous(info);
+    }));
+}
+
+/// Give the user's shell back its normal screen
+pub fn leave() -> Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    Ok(())
+}
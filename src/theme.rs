@@ -0,0 +1,191 @@
+use std::env;
+
+use ratatui::style::{Color, Style, Stylize};
+
+use crate::app::Severity;
+
+/// The built-in themes in the order `T` cycles through them
+pub const THEME_NAMES: [&str; 4] = ["default", "light", "high-contrast", "plain"];
+
+/// Every style the UI draws with, grouped by the role it plays
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// One of `THEME_NAMES`
+    pub name: &'static str,
+    /// Keybinding letters, detail values
+    pub accent: Style,
+    /// Column header text
+    pub header: Style,
+    /// The selected table row
+    pub highlight: Style,
+    /// Ordinary cell and popup text
+    pub text: Style,
+    /// The active search query
+    pub filter: Style,
+    /// The part of a process name the search matched
+    pub search_match: Style,
+    /// Secondary information like counts
+    pub dim: Style,
+    /// Popup backgrounds
+    pub popup: Style,
+    pub success: Style,
+    pub warning: Style,
+    pub danger: Style,
+    /// Rows of processes that are stopped (frozen)
+    pub stopped: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "default",
+            accent: Style::default().fg(Color::Yellow),
+            header: Style::default().fg(Color::Green),
+            highlight: Style::default().fg(Color::Yellow).bold(),
+            text: Style::default().fg(Color::White),
+            filter: Style::default().fg(Color::Blue),
+            search_match: Style::default().fg(Color::Black).bg(Color::Cyan),
+            dim: Style::default().fg(Color::Gray),
+            popup: Style::default().bg(Color::DarkGray),
+            success: Style::default().fg(Color::Green),
+            warning: Style::default().fg(Color::Yellow),
+            danger: Style::default().fg(Color::Red),
+            stopped: Style::default().bg(Color::Blue),
+        }
+    }
+}
+
+impl Theme {
+    /// Dark text for terminals with a light background
+    pub fn light() -> Self {
+        Self {
+            name: "light",
+            accent: Style::default().fg(Color::Blue),
+            header: Style::default().fg(Color::Green).bold(),
+            highlight: Style::default().fg(Color::Blue).bold(),
+            text: Style::default().fg(Color::Black),
+            filter: Style::default().fg(Color::Magenta),
+            search_match: Style::default().bg(Color::LightYellow),
+            dim: Style::default().fg(Color::DarkGray),
+            popup: Style::default().bg(Color::Gray),
+            success: Style::default().fg(Color::Green),
+            // Yellow is unreadable on white, a dark orange isn't
+            warning: Style::default().fg(Color::Indexed(130)),
+            danger: Style::default().fg(Color::Red),
+            stopped: Style::default().bg(Color::LightBlue),
+        }
+    }
+
+    /// Bold, saturated colors. Warning and danger differ in background and weight as well as
+    /// hue, so the memory and CPU thresholds stay apart without telling similar colors apart.
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast",
+            accent: Style::default().fg(Color::LightYellow).bold(),
+            header: Style::default().fg(Color::White).bold().underlined(),
+            highlight: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .bold(),
+            text: Style::default().fg(Color::White),
+            filter: Style::default().fg(Color::LightCyan).bold(),
+            search_match: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightCyan)
+                .bold(),
+            dim: Style::default().fg(Color::White),
+            popup: Style::default().fg(Color::White).bg(Color::Black),
+            success: Style::default().fg(Color::LightGreen).bold(),
+            warning: Style::default().fg(Color::LightYellow).bold(),
+            danger: Style::default().fg(Color::White).bg(Color::Red).bold(),
+            stopped: Style::default().fg(Color::White).bg(Color::Blue),
+        }
+    }
+
+    /// No colors or modifiers at all, the highlight symbol alone marks the selection
+    pub fn plain() -> Self {
+        Self {
+            name: "plain",
+            accent: Style::default(),
+            header: Style::default(),
+            highlight: Style::default(),
+            text: Style::default(),
+            filter: Style::default(),
+            search_match: Style::default(),
+            dim: Style::default(),
+            popup: Style::default(),
+            success: Style::default(),
+            warning: Style::default(),
+            danger: Style::default(),
+            stopped: Style::default(),
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "default" | "dark" => Some(Self::default()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "plain" => Some(Self::plain()),
+            _ => None,
+        }
+    }
+
+    /// Pick the plain theme when the environment asks for it or the terminal can't show color
+    pub fn detect() -> Self {
+        Self::detect_named(None)
+    }
+
+    /// Like `detect`, but use the named theme when colors are available
+    pub fn detect_named(name: Option<&str>) -> Self {
+        if colors_disabled() {
+            Self::plain()
+        } else {
+            name.and_then(Self::by_name).unwrap_or_default()
+        }
+    }
+
+    /// The theme after this one in `THEME_NAMES`
+    pub fn next(&self) -> Self {
+        let index = THEME_NAMES.iter().position(|&name| name == self.name);
+        let next = index.map_or(0, |i| (i + 1) % THEME_NAMES.len());
+        Self::by_name(THEME_NAMES[next]).unwrap_or_default()
+    }
+
+    pub fn message(&self, severity: Severity) -> Style {
+        match severity {
+            Severity::Success => self.success,
+            Severity::Error => self.danger,
+            Severity::Info => self.text,
+        }
+    }
+}
+
+/// Whether `NO_COLOR`, a dumb terminal or one whose terminfo lists no colors turned colors off
+pub fn colors_disabled() -> bool {
+    // https://no-color.org: any non-empty value opts out
+    if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return true;
+    }
+
+    // Windows consoles don't set TERM, everywhere else a missing TERM means a bare environment
+    match env::var("TERM") {
+        Ok(term) => term == "dumb" || too_few_colors(&term),
+        Err(_) => !cfg!(target_os = "windows"),
+    }
+}
+
+/// The terminfo entry for `term` has fewer than the 8 basic colors, or no `colors` at all like
+/// `vt100`. A terminal without an entry keeps its colors, most of them have plenty.
+#[cfg(unix)]
+fn too_few_colors(term: &str) -> bool {
+    termini::TermInfo::from_name(term).is_ok_and(|info| {
+        info.number_cap(termini::NumberCapability::MaxColors)
+            .is_none_or(|colors| colors < 8)
+    })
+}
+
+#[cfg(not(unix))]
+fn too_few_colors(_term: &str) -> bool {
+    false
+}
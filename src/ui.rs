@@ -1,186 +1,936 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ratatui::{
     Frame, Terminal,
     backend::Backend,
-    crossterm::event::{self, Event, KeyCode},
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    prelude::Color,
-    style::{Style, Stylize},
-    text::Line,
-    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table},
+    crossterm::event::{Event, KeyCode, MouseButton, MouseEvent, MouseEventKind},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Style, Styled, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, Gauge, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, Table, TableState, Wrap,
+    },
 };
-use std::time::Duration;
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    ops::Range,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use sysinfo::ProcessStatus;
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::{App, InputMode, SortColumn};
-use crate::utils::centered_rect;
+use crate::app::{
+    App, Column, DetailsLayout, InputMode, KillSignal, Severity, SortColumn, StatusFilter,
+};
+use crate::config::NameTruncation;
+use crate::input::InputThread;
+use crate::keymap::{Action, Keymap};
+use crate::system_data::{
+    FD_COUNT_SUPPORTED, NET_SUPPORTED, ProcessInfo, THREADS_SUPPORTED, TreeRow, byte_rate,
+    is_blocked_on_io, state_code,
+};
+use crate::terminal;
+use crate::theme::Theme;
+use crate::utils::{
+    centered_rect, centered_rect_pct, format_bytes, format_elapsed, format_mb, format_timestamp,
+    format_uptime, truncate_end, truncate_middle,
+};
+
+/// Clicks select a row and the wheel scrolls, but only while no popup is open
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    if app.input_mode != InputMode::Normal {
+        return;
+    }
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => app.click(mouse.column, mouse.row),
+        MouseEventKind::ScrollDown => app.next(),
+        MouseEventKind::ScrollUp => app.previous(),
+        _ => {}
+    }
+}
 
-/// Main app logic
-pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<()> {
+/// Main app logic. Errors returned from here end the app, so only the fatal ones use `?`:
+/// drawing, reading input and taking over the terminal again, which all mean the terminal
+/// itself is unusable. Anything a single action can get wrong ends up in `App::report_error`.
+pub fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    keymap: &Keymap,
+) -> Result<()> {
+    let input = InputThread::spawn();
     loop {
         app.refresh();
-        terminal.draw(|f| ui(f, &mut app))?;
-
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match app.input_mode {
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('q') => return Ok(()),
-                        KeyCode::Down => app.next(),
-                        KeyCode::Up => app.previous(),
-                        KeyCode::Char('k') => app.input_mode = InputMode::ConfirmKill,
-                        KeyCode::Char('/') => {
-                            app.input_mode = InputMode::Search;
-                            app.search_query.clear();
-                        }
-                        KeyCode::Char('p') => app.toggle_sort(SortColumn::Pid),
-                        KeyCode::Char('n') => app.toggle_sort(SortColumn::Name),
-                        KeyCode::Char('m') => app.toggle_sort(SortColumn::Memory),
-                        _ => {}
-                    },
-                    InputMode::Search => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.search_query.clear();
-                            app.apply_filters();
-                        }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::Normal;
-                            app.apply_filters();
-                        }
-                        KeyCode::Backspace => {
-                            app.search_query.pop();
-                            app.apply_filters();
+        let drawing = Instant::now();
+        terminal.draw(|f| ui(f, &mut app, keymap))?;
+        app.debug.record_frame(drawing);
+
+        // Sleep until a key arrives or there's something new to show
+        if let Some(event) = input.recv_timeout(app.next_wakeup())? {
+            app.debug.record_event();
+            let key = match event {
+                Event::Key(key) => key,
+                Event::Mouse(mouse) => {
+                    handle_mouse(&mut app, mouse);
+                    continue;
+                }
+                // Redraw from scratch right away, the layout is recomputed on every draw
+                Event::Resize(_, _) => {
+                    terminal.autoresize()?;
+                    terminal.clear()?;
+                    continue;
+                }
+                _ => continue,
+            };
+            match app.input_mode {
+                InputMode::Normal => match keymap.action(&key) {
+                    Some(Action::Quit) if app.request_quit() => break,
+                    Some(Action::Next) => app.next(),
+                    Some(Action::Previous) => app.previous(),
+                    Some(Action::First) => app.go_first(),
+                    Some(Action::Last) => app.go_last(),
+                    Some(Action::HalfPageDown) => app.half_page_down(),
+                    Some(Action::HalfPageUp) => app.half_page_up(),
+                    Some(Action::Kill) => app.begin_kill(),
+                    Some(Action::Mark) => app.toggle_marked(),
+                    Some(Action::Pin) => app.toggle_pinned(),
+                    #[cfg(unix)]
+                    Some(Action::Renice) => app.begin_renice(),
+                    Some(Action::Command) => app.begin_command(),
+                    Some(Action::Help) => app.input_mode = InputMode::Help,
+                    Some(Action::Columns) => app.open_column_menu(),
+                    #[cfg(unix)]
+                    Some(Action::Freeze) => app.toggle_frozen(),
+                    Some(Action::Search) => {
+                        app.input_mode = InputMode::Search;
+                        app.search_query.clear();
+                    }
+                    Some(Action::SortPid) => app.toggle_sort(SortColumn::Pid),
+                    Some(Action::SortName) => app.toggle_sort(SortColumn::Name),
+                    Some(Action::SortMemory) => app.toggle_sort(SortColumn::Memory),
+                    Some(Action::SortCpu) => app.toggle_sort(SortColumn::Cpu),
+                    Some(Action::SortFds) => app.toggle_sort(SortColumn::Fds),
+                    Some(Action::SortThreads) => app.toggle_sort(SortColumn::Threads),
+                    Some(Action::ThreadsColumn) => app.toggle_threads_column(),
+                    Some(Action::SortDiskIo) => app.toggle_sort(SortColumn::DiskIo),
+                    Some(Action::SortUptime) => app.toggle_sort(SortColumn::Uptime),
+                    Some(Action::DiskColumns) => app.toggle_disk_columns(),
+                    Some(Action::FdColumn) => app.toggle_fd_column(),
+                    Some(Action::ThreadList) => app.toggle_threads(),
+                    Some(Action::Environment) => app.toggle_environment(),
+                    Some(Action::StatusFilter) => app.cycle_status_filter(),
+                    Some(Action::MemoryFilter) => app.cycle_memory_filter(),
+                    Some(Action::KernelThreads) => app.toggle_kernel_threads(),
+                    Some(Action::MyProcesses) => app.toggle_my_processes(),
+                    Some(Action::Layout) => app.next_preset(),
+                    Some(Action::Theme) => app.cycle_theme(),
+                    Some(Action::Tree) => app.toggle_tree_view(),
+                    Some(Action::PerCoreCpu) => app.toggle_per_core_cpu(),
+                    Some(Action::DetailsLayout) => app.cycle_details_layout(),
+                    Some(Action::ExportJson) => app.export_json(),
+                    Some(Action::ExportCsv) => app.export_csv(),
+                    Some(Action::OpenFolder) => app.open_exe_folder(),
+                    Some(Action::NextPage) => app.next_page(),
+                    Some(Action::PreviousPage) => app.previous_page(),
+                    Some(Action::ScrollDetailsDown) => app.scroll_details_down(),
+                    Some(Action::ScrollDetailsUp) => app.scroll_details_up(),
+                    Some(Action::Fold) if app.tree_view => app.toggle_collapsed(),
+                    Some(Action::SlowerRefresh) => app.slower_refresh(),
+                    Some(Action::FasterRefresh) => app.faster_refresh(),
+                    Some(Action::SetInterval) => app.begin_set_interval(),
+                    Some(Action::SnapshotGone) if app.snapshot.is_some() => {
+                        app.input_mode = InputMode::SnapshotGone
+                    }
+                    Some(Action::RunCommand) => run_custom_command(terminal, &mut app, &input)?,
+                    Some(Action::Log) => app.open_log(),
+                    Some(Action::Debug) => app.show_debug = !app.show_debug,
+                    _ => {}
+                },
+                InputMode::Search => match key.code {
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                        app.search_query.clear();
+                        app.refilter();
+                    }
+                    KeyCode::Enter => app.finish_search(),
+                    KeyCode::Tab => app.toggle_fuzzy_search(),
+                    KeyCode::Backspace => {
+                        app.search_query.pop();
+                        app.refilter();
+                    }
+                    KeyCode::Char(c) => {
+                        app.search_query.push(c);
+                        app.refilter();
+                    }
+                    _ => {}
+                },
+                InputMode::ConfirmKill => match key.code {
+                    KeyCode::Up => app.cycle_kill_signal(false),
+                    KeyCode::Down => app.cycle_kill_signal(true),
+                    // Is this better than 'n' for "No"?
+                    KeyCode::Char('y') | KeyCode::Enter => app.kill_target_process(),
+                    _ => app.cancel_kill(),
+                },
+                InputMode::ConfirmQuit => match key.code {
+                    KeyCode::Char('y') => break,
+                    _ => app.input_mode = InputMode::Normal,
+                },
+                InputMode::ConfirmJump => match key.code {
+                    KeyCode::Char('y') | KeyCode::Enter => app.confirm_jump(),
+                    _ => {
+                        app.jump_target = None;
+                        app.input_mode = InputMode::Normal;
+                    }
+                },
+                InputMode::SnapshotGone | InputMode::Help => app.input_mode = InputMode::Normal,
+                InputMode::SetInterval => match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    KeyCode::Enter => app.apply_interval(),
+                    KeyCode::Backspace => {
+                        app.interval_input.pop();
+                        app.interval_error = None;
+                    }
+                    KeyCode::Char(c) => {
+                        app.interval_input.push(c);
+                        app.interval_error = None;
+                    }
+                    _ => {}
+                },
+                InputMode::Log => match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_log_up(1),
+                    KeyCode::Down | KeyCode::Char('j') => app.scroll_log_down(1),
+                    KeyCode::PageUp => app.scroll_log_up(app.log_rows),
+                    KeyCode::PageDown => app.scroll_log_down(app.log_rows),
+                    KeyCode::Esc | KeyCode::Char('l') | KeyCode::Char('q') => {
+                        app.input_mode = InputMode::Normal
+                    }
+                    _ => {}
+                },
+                InputMode::ColumnMenu => match key.code {
+                    KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => {
+                        app.input_mode = InputMode::Normal
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => app.column_menu_next(),
+                    KeyCode::Up | KeyCode::Char('k') => app.column_menu_previous(),
+                    KeyCode::Char(' ') | KeyCode::Enter => app.toggle_menu_column(),
+                    _ => {}
+                },
+                InputMode::Command => match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    KeyCode::Enter => app.run_command(),
+                    KeyCode::Backspace => {
+                        app.command_input.pop();
+                    }
+                    KeyCode::Char(c) => app.command_input.push(c),
+                    _ => {}
+                },
+                #[cfg(unix)]
+                InputMode::Renice => match key.code {
+                    KeyCode::Esc => app.cancel_renice(),
+                    KeyCode::Enter => app.apply_renice(),
+                    KeyCode::Backspace => {
+                        if let Some(target) = app.renice_target.as_mut() {
+                            target.input.pop();
                         }
-                        KeyCode::Char(c) => {
-                            app.search_query.push(c);
-                            app.apply_filters();
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => {
+                        if let Some(target) = app.renice_target.as_mut() {
+                            target.input.push(c);
                         }
-                        _ => {}
-                    },
-                    InputMode::ConfirmKill => match key.code {
-                        // Is this better than 'n' for "No"?
-                        KeyCode::Char('y') => app.kill_selected_process(),
-                        _ => app.input_mode = InputMode::Normal,
-                    },
-                }
+                    }
+                    _ => {}
+                },
             }
         }
     }
+
+    app.save_preferences().context("Failed to save preferences")
 }
 
-pub fn ui(f: &mut Frame, app: &mut App) {
+/// Hand the terminal over to the configured command, then take it back however the command ended
+fn run_custom_command<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    input: &InputThread,
+) -> Result<()> {
+    let Some(command) = app.selected_command() else {
+        if app.custom_command.is_none() {
+            app.set_message(
+                "No command configured, set `command` in config.toml".to_string(),
+                Severity::Error,
+            );
+        }
+        return Ok(());
+    };
+
+    // The command and the prompt below read stdin themselves
+    let _paused = input.pause();
+    terminal::leave()?;
+    terminal.show_cursor()?;
+
+    println!("$ {command}");
+    let status = shell(&command).status();
+    // Not being able to wait for Enter only means the output flashes by
+    let mut prompt = Ok(());
+    if status.is_ok() {
+        // Keep the output on screen until the user has read it
+        print!("\nPress Enter to return...");
+        prompt = io::stdout()
+            .flush()
+            .and_then(|()| io::stdin().read_line(&mut String::new()).map(drop));
+    }
+
+    let restored = terminal::enter();
+    terminal.clear()?; // The old frame is gone, force a full redraw
+    restored?;
+
+    match status {
+        Ok(status) if status.success() => {
+            app.set_message(format!("`{command}` finished"), Severity::Success)
+        }
+        Ok(status) => app.set_message(format!("`{command}` {status}"), Severity::Error),
+        Err(e) => app.set_message(format!("Failed to run `{command}`: {e}"), Severity::Error),
+    }
+    if let Err(e) = prompt {
+        app.report_error(&anyhow::Error::new(e).context("Failed to wait for Enter"));
+    }
+    app.force_refresh();
+
+    Ok(())
+}
+
+fn shell(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    shell.arg(command);
+    shell
+}
+
+pub fn ui(f: &mut Frame, app: &mut App, keymap: &Keymap) {
+    // Taller with per-core gauges, the margin takes a column on each side
+    let summary_height = summary_height(app, f.area().width.saturating_sub(2));
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(3),     // Process table
-            Constraint::Length(10), // Process details
-            Constraint::Length(3),  // Help bar
+            Constraint::Length(summary_height), // System summary
+            Constraint::Min(3),                 // Process table
+            details_constraint(app),            // Process details, 0 when the preset hides it
+            Constraint::Length(3),              // Help bar
         ])
         .margin(1)
         .split(f.area());
 
-    render_process_table(f, app, chunks[0]);
+    render_summary_bar(f, app, chunks[0]);
+    render_process_table(f, app, chunks[1]);
 
-    render_process_details(f, app, chunks[1]);
+    if app.details_height > 0 {
+        render_process_details(f, app, chunks[2]);
+    }
 
-    render_help_bar(f, app, chunks[2]);
+    render_help_bar(f, app, keymap, chunks[3]);
 
     // Popups
     match app.input_mode {
         InputMode::Search => render_search_popup(f, app),
         InputMode::ConfirmKill => render_kill_confirmation(f, app),
+        InputMode::ConfirmQuit => render_quit_confirmation(f, app),
+        InputMode::ConfirmJump => render_jump_confirmation(f, app),
+        InputMode::SnapshotGone => render_snapshot_gone(f, app),
+        #[cfg(unix)]
+        InputMode::Renice => render_renice_popup(f, app),
+        InputMode::Command => render_command_popup(f, app),
+        InputMode::Help => render_help_overlay(f, app, keymap),
+        InputMode::ColumnMenu => render_column_menu(f, app),
+        InputMode::Log => render_log(f, app),
+        InputMode::SetInterval => render_interval_popup(f, app),
         _ => {}
     }
 
     // Show message if any
-    if let Some((message, color)) = &app.message {
-        render_message(f, message, *color);
+    if let Some((message, severity)) = &app.message {
+        render_message(f, message, app.theme.message(*severity));
+    }
+
+    if app.show_debug {
+        render_debug_overlay(f, app);
+    }
+}
+
+/// Timings in the top right corner, drawn dim so the table underneath stays readable
+fn render_debug_overlay(f: &mut Frame, app: &App) {
+    let debug = &app.debug;
+    let ms = |duration: Duration| format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+    let lines = vec![
+        Line::from(format!(
+            "Frame    {} (avg {})",
+            ms(debug.last_frame()),
+            ms(debug.average_frame())
+        )),
+        Line::from(format!("Draws/s  {}", debug.frames_per_second())),
+        Line::from(format!("Latency  {}", ms(debug.event_latency))),
+        Line::from(format!("Refresh  {}", ms(debug.refresh_took))),
+        Line::from(format!(
+            "Procs    {} ({} shown)",
+            app.processes.len(),
+            app.filtered_processes.len()
+        )),
+    ];
+
+    let width = 34.min(f.area().width);
+    let height = (lines.len() as u16 + 2).min(f.area().height);
+    let area = Rect {
+        x: f.area().width - width,
+        y: 0,
+        width,
+        height,
+    };
+
+    f.render_widget(Clear, area);
+    let text = Paragraph::new(lines).style(app.theme.dim).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(" Debug (F12) ")
+            .border_style(app.theme.dim),
+    );
+    f.render_widget(text, area);
+}
+
+/// Narrowest a per-core gauge gets, enough for a label like `12-15 100%`
+const CORE_GAUGE_WIDTH: u16 = 12;
+/// Past this many rows of per-core gauges, neighbouring cores share a gauge instead
+const MAX_CORE_ROWS: usize = 4;
+
+/// How the per-core gauges fit in `width` columns
+struct CoreGrid {
+    /// Cores averaged into each gauge, 1 unless there are too many to show one by one
+    group_size: usize,
+    columns: usize,
+    rows: usize,
+}
+
+impl CoreGrid {
+    fn new(cores: usize, width: u16) -> Self {
+        let columns = ((width / CORE_GAUGE_WIDTH) as usize).max(1);
+        let group_size = cores.div_ceil(columns * MAX_CORE_ROWS).max(1);
+        let gauges = cores.div_ceil(group_size);
+        Self {
+            group_size,
+            columns: columns.min(gauges).max(1),
+            rows: gauges.div_ceil(columns),
+        }
+    }
+}
+
+/// One line for RAM and overall CPU, plus the per-core gauges when they are on
+fn summary_height(app: &App, width: u16) -> u16 {
+    if app.per_core_cpu {
+        1 + CoreGrid::new(app.summary.per_core.len(), width).rows as u16
+    } else {
+        1
+    }
+}
+
+/// RAM gauge and overall CPU load of the whole machine
+fn render_summary_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let summary = &app.summary;
+    let [overall_area, cores_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+    let [cpu_area, memory_area] =
+        Layout::horizontal([Constraint::Length(16), Constraint::Fill(1)]).areas(overall_area);
+
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            " CPU ".set_style(theme.header),
+            format!("{:.1} %", summary.cpu_percent).set_style(theme.accent),
+        ])),
+        cpu_area,
+    );
+
+    let ratio = if summary.total_mem_mb > 0.0 {
+        (summary.used_mem_mb / summary.total_mem_mb).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let gauge_style = if ratio > 0.9 {
+        theme.danger
+    } else if ratio > 0.7 {
+        theme.warning
+    } else {
+        theme.success
+    };
+    let gauge = Gauge::default()
+        .gauge_style(gauge_style)
+        .ratio(ratio)
+        .label(format!(
+            "RAM {} / {} ({} free)",
+            format_mb(summary.used_mem_mb),
+            format_mb(summary.total_mem_mb),
+            format_mb(summary.total_mem_mb - summary.used_mem_mb)
+        ));
+    f.render_widget(gauge, memory_area);
+
+    if app.per_core_cpu {
+        render_core_gauges(f, app, cores_area);
+    }
+}
+
+/// A grid of small gauges, one per core or per group of cores on big machines
+fn render_core_gauges(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let cores = &app.summary.per_core;
+    let grid = CoreGrid::new(cores.len(), area.width);
+
+    let rows = Layout::vertical(vec![Constraint::Length(1); grid.rows]).split(area);
+    for (n, group) in cores.chunks(grid.group_size).enumerate() {
+        let Some(&row) = rows.get(n / grid.columns) else {
+            break;
+        };
+        let cells = Layout::horizontal(vec![Constraint::Fill(1); grid.columns])
+            .spacing(1)
+            .split(row);
+
+        let usage = group.iter().sum::<f32>() / group.len() as f32;
+        let first = n * grid.group_size;
+        let label = if group.len() == 1 {
+            first.to_string()
+        } else {
+            format!("{first}-{}", first + group.len() - 1)
+        };
+        let style = if usage > 80.0 {
+            theme.danger
+        } else if usage > 40.0 {
+            theme.warning
+        } else {
+            theme.success
+        };
+        let gauge = Gauge::default()
+            .gauge_style(style)
+            .ratio((usage as f64 / 100.0).clamp(0.0, 1.0))
+            .label(format!("{label} {usage:.0}%"));
+        f.render_widget(gauge, cells[n % grid.columns]);
     }
 }
 
+/// Told apart from a filter that matches nothing, which still has processes behind it
+const NO_PROCESSES: &str = "No processes available — check permissions";
+
 fn render_process_table(f: &mut Frame, app: &mut App, area: Rect) {
+    app.table_area = area;
+    if app.processes.is_empty() {
+        app.header_cells.clear();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title("Process Information");
+        let [_, message_area, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(block.inner(area));
+        f.render_widget(block, area);
+        f.render_widget(
+            Paragraph::new(NO_PROCESSES)
+                .style(app.theme.warning)
+                .alignment(Alignment::Center),
+            message_area,
+        );
+        return;
+    }
+    // Borders take two rows, the header and its bottom margin another two
+    let visible_rows = area.height.saturating_sub(4) as usize;
+    if app.page_size > 0 {
+        app.apply_paging(visible_rows);
+    } else {
+        app.apply_scrolloff(visible_rows);
+    }
+
+    let theme = app.theme;
     let sort_indicator = |column| {
-        if app.sort_column == column {
+        if Some(app.sort_column) == column {
             if app.sort_ascending { " ↑" } else { " ↓" }
         } else {
             ""
         }
     };
-    let pid_sort = sort_indicator(SortColumn::Pid);
-
-    let name_sort = sort_indicator(SortColumn::Name);
-    let mem_sort = sort_indicator(SortColumn::Memory);
-
     // Make it similar to something I saw
-    let header_cells = [
-        Cell::from(Line::from(vec![
-            "P".fg(Color::Yellow),
-            "ID".fg(Color::Green),
-            pid_sort.fg(Color::Green),
-        ])),
-        Cell::from(Line::from(vec![
-            "N".fg(Color::Yellow),
-            "ame".fg(Color::Green),
-            name_sort.fg(Color::Green),
-        ])),
-        Cell::from(Line::from(vec![
-            "M".fg(Color::Yellow),
-            "emory (MB)".fg(Color::Green),
-            mem_sort.fg(Color::Green),
-        ])),
-    ];
+    let mut header_cells: Vec<Cell> = app
+        .columns
+        .iter()
+        .map(|&column| {
+            let (before, key, rest) = column.header();
+            Cell::from(Line::from(vec![
+                before.set_style(theme.header),
+                key.set_style(theme.accent),
+                rest.set_style(theme.header),
+                sort_indicator(column.sort_column()).set_style(theme.header),
+            ]))
+        })
+        .collect();
+    if app.snapshot.is_some() {
+        header_cells.push(Cell::from("Since snapshot".set_style(theme.header)));
+    }
+    // Only take up room for the marks column while something is marked or pinned
+    let show_marks = !app.marked.is_empty();
+    let show_pins = !app.pinned.is_empty();
+    if show_marks || show_pins {
+        header_cells.insert(0, Cell::from(""));
+    }
 
     let header = Row::new(header_cells)
         .style(Style::default())
         .height(1)
         .bottom_margin(1);
 
-    let rows = app.filtered_processes.iter().map(|&i| {
-        let process = &app.processes[i];
-        let mem_color = if process.memory_mb > 500.0 {
-            Color::Red
-        } else if process.memory_mb > 100.0 {
-            Color::Yellow
-        } else {
-            Color::White
-        };
+    // Only the rows that fit on screen are built, starting at the offset scrolloff or paging
+    // settled on above. The page (if any) may end before the screen does.
+    let page = app.page_range();
+    let end = page
+        .as_ref()
+        .map_or(app.filtered_processes.len(), |page| page.end);
+    let first = app.table_state.offset().min(end);
+    let last = end.min(first + app.visible_rows);
 
-        let cells = [
-            Cell::from(process.pid.clone()),
-            Cell::from(process.name.clone()),
-            Cell::from(format!("{:.2}", process.memory_mb)).style(Style::default().fg(mem_color)),
-        ];
-        Row::new(cells).height(1)
-    });
+    // Fill shares the width in proportion, so optional columns don't push past 100%
+    let mut widths: Vec<Constraint> = app
+        .columns
+        .iter()
+        .map(|column| Constraint::Fill(column.weight()))
+        .collect();
+    if app.snapshot.is_some() {
+        widths.push(Constraint::Fill(20));
+    }
+    if show_marks || show_pins {
+        // The pin is double width
+        widths.insert(
+            0,
+            Constraint::Length(u16::from(show_marks) + 2 * u16::from(show_pins)),
+        );
+    }
 
-    let title = format!(
+    // The rows start at the window, so the table sees the selection relative to it
+    let window_selected = app
+        .table_state
+        .selected()
+        .and_then(|selected| selected.checked_sub(first))
+        .filter(|&selected| selected < last - first);
+    let column_areas = column_areas(app, area, &widths, window_selected.is_some());
+    let name_width = app
+        .columns
+        .iter()
+        .zip(&column_areas)
+        .find(|(column, _)| **column == Column::Name)
+        .map(|(_, cell)| cell.width);
+
+    // Highlights are looked up in the shortened names, a match that was cut off isn't shown
+    let names: Vec<String> = app.filtered_processes[first..last]
+        .iter()
+        .enumerate()
+        .map(|(n, &i)| {
+            let process = &app.processes[i];
+            let tree_row = app.tree_rows.get(first + n);
+            name_width.map_or_else(
+                || process.name.clone(),
+                |width| display_name(process, tree_row, width, app.name_truncation),
+            )
+        })
+        .collect();
+    let highlights: Vec<Vec<Range<usize>>> = names
+        .iter()
+        .map(|name| app.search_highlights(name))
+        .collect();
+    let rows = app.filtered_processes[first..last]
+        .iter()
+        .zip(names.iter().zip(&highlights))
+        .enumerate()
+        .map(|(n, (&i, (name, highlights)))| {
+            let process = &app.processes[i];
+            let tree_row = app.tree_rows.get(first + n);
+            let mut cells: Vec<Cell> = app
+                .columns
+                .iter()
+                .map(|&column| {
+                    column_cell(
+                        process,
+                        column,
+                        tree_row,
+                        name,
+                        highlights,
+                        app.disk_interval,
+                        &theme,
+                    )
+                })
+                .collect();
+            if app.snapshot.is_some() {
+                let diff = &app.snapshot_diff;
+                let cell = if diff.new.contains(&process.pid) {
+                    Cell::from("new").style(theme.success)
+                } else if let Some(delta) = diff.changed.get(&process.pid) {
+                    Cell::from(format!("{delta:+.2} MB")).style(theme.warning)
+                } else {
+                    Cell::from("")
+                };
+                cells.push(cell);
+            }
+            if show_marks || show_pins {
+                let mark = match (show_marks, app.marked.contains(&process.pid)) {
+                    (_, true) => "✓",
+                    (true, false) => " ",
+                    (false, false) => "",
+                };
+                let pin = if app.pinned.contains(&process.pid) {
+                    "📌"
+                } else {
+                    ""
+                };
+                cells.insert(0, Cell::from(format!("{mark}{pin}")).style(theme.success));
+            }
+            let row = Row::new(cells).height(1);
+            if process.status == ProcessStatus::Stop {
+                row.style(theme.stopped)
+            } else {
+                row
+            }
+        });
+
+    let mut title = format!(
         "Process Information ({} processes)",
         app.filtered_processes.len()
     );
+    if app.status_filter != StatusFilter::All {
+        title.push_str(&format!(" · only {}", app.status_filter.label()));
+    }
+    if let Some(min) = app.min_memory_mb {
+        title.push_str(&format!(" · ≥ {}", format_mb(min)));
+    }
+    if app.hidden_kernel_threads > 0 {
+        title.push_str(&format!(
+            " · {} kernel threads hidden",
+            app.hidden_kernel_threads
+        ));
+    }
+    if let Some(user) = &app.user_filter {
+        title.push_str(&format!(" · user {user}"));
+    }
+    if app.snapshot.is_some() {
+        let diff = &app.snapshot_diff;
+        title.push_str(&format!(
+            " · vs snapshot: {} new, {} changed, {} gone (d) ",
+            diff.new.len(),
+            diff.changed.len(),
+            diff.gone.len()
+        ));
+    }
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Percentage(15),
-            Constraint::Percentage(55),
-            Constraint::Percentage(30),
-        ],
-    )
-    .header(header)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .title(title),
-    )
-    .row_highlight_style(Style::default().fg(Color::Yellow).bold())
-    .highlight_symbol("> ");
+    // Impossible to miss, so nobody wonders why their kills don't work
+    let mut title = Line::from(title);
+    if app.dry_run {
+        title
+            .spans
+            .insert(0, " DRY RUN ".set_style(theme.danger.reversed()));
+        title.spans.insert(1, " ".into());
+    }
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title);
+    if page.is_some() {
+        block = block.title_bottom(format!(
+            " Page {}/{} ",
+            app.current_page + 1,
+            app.page_count()
+        ));
+    }
+
+    let table = Table::new(rows, widths.clone())
+        .header(header)
+        .block(block)
+        .row_highlight_style(theme.highlight)
+        .highlight_symbol("> ");
+
+    let mut window_state = TableState::default().with_selected(window_selected);
+    app.header_cells = app
+        .columns
+        .iter()
+        .zip(&column_areas)
+        .filter_map(|(column, cell)| Some((cell.x..cell.x + cell.width, column.sort_column()?)))
+        .collect();
+    f.render_stateful_widget(table, area, &mut window_state);
 
-    f.render_stateful_widget(table, area, &mut app.table_state);
+    // Drawn over the right border, and only when there is something to scroll
+    let total = app.filtered_processes.len();
+    if page.is_none() && total > app.visible_rows {
+        let mut scrollbar_state =
+            ScrollbarState::new(total).position(app.table_state.selected().unwrap_or(0));
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight).style(theme.dim),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+/// Where each of the app's columns lands on screen, worked out the way `Table` lays out its
+/// columns: the highlight symbol's width first (only while a row is selected), then the
+/// widths with one column of spacing between them
+fn column_areas(app: &App, area: Rect, widths: &[Constraint], selected: bool) -> Vec<Rect> {
+    let inner = area.inner(Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    let symbol_width = if selected { 2 } else { 0 };
+    let [_, columns_area] =
+        Layout::horizontal([Constraint::Length(symbol_width), Constraint::Fill(0)]).areas(inner);
+    let cells = Layout::horizontal(widths.to_vec())
+        .spacing(1)
+        .split(columns_area);
+
+    // The marks column, when there is one, comes before the app's columns
+    let skip = cells
+        .len()
+        .saturating_sub(app.columns.len() + usize::from(app.snapshot.is_some()));
+    cells
+        .iter()
+        .skip(skip)
+        .take(app.columns.len())
+        .copied()
+        .collect()
+}
+
+/// The process name cut to what's left of the name column after the tree prefix and `[D]`
+fn display_name(
+    process: &ProcessInfo,
+    tree_row: Option<&TreeRow>,
+    width: u16,
+    truncation: NameTruncation,
+) -> String {
+    let mut room = usize::from(width);
+    if let Some(row) = tree_row {
+        room = room.saturating_sub(row.prefix.width());
+        if row.collapsed || row.has_children {
+            room = room.saturating_sub(2);
+        }
+    }
+    if is_blocked_on_io(process.status) {
+        room = room.saturating_sub(4);
+    }
+    match truncation {
+        NameTruncation::End => truncate_end(&process.name, room),
+        NameTruncation::Middle => truncate_middle(&process.name, room),
+    }
+}
+
+/// `name` is the process name as it fits the column, `highlights` are the byte ranges of it
+/// the search matched, they get a background. `disk_interval` is the time the disk byte counts
+/// cover.
+fn column_cell<'a>(
+    process: &'a ProcessInfo,
+    column: Column,
+    tree_row: Option<&TreeRow>,
+    name: &'a str,
+    highlights: &[Range<usize>],
+    disk_interval: Duration,
+    theme: &Theme,
+) -> Cell<'a> {
+    match column {
+        Column::Pid => Cell::from(process.pid.to_string()),
+        Column::Status => {
+            let (glyph, style) = match process.status {
+                ProcessStatus::Run => ("●", theme.success),
+                ProcessStatus::Sleep | ProcessStatus::Idle => ("○", theme.dim),
+                ProcessStatus::UninterruptibleDiskSleep => ("◐", theme.danger),
+                ProcessStatus::Zombie | ProcessStatus::Dead => ("☠", theme.danger),
+                ProcessStatus::Stop | ProcessStatus::Tracing => ("⏸", theme.warning),
+                _ => ("·", theme.text),
+            };
+            Cell::from(glyph).style(style)
+        }
+        Column::Name => {
+            let mut line = Line::default();
+            if let Some(row) = tree_row {
+                line.push_span(row.prefix.clone().set_style(theme.dim));
+                if row.collapsed {
+                    line.push_span("▸ ".set_style(theme.accent));
+                } else if row.has_children {
+                    line.push_span("▾ ".set_style(theme.accent));
+                }
+            }
+            let mut end = 0;
+            for range in highlights {
+                line.push_span(&name[end..range.start]);
+                line.push_span(name[range.clone()].set_style(theme.search_match));
+                end = range.end;
+            }
+            line.push_span(&name[end..]);
+            if is_blocked_on_io(process.status) {
+                line.push_span(" [D]".set_style(theme.danger));
+            }
+            Cell::from(line)
+        }
+        Column::Memory => {
+            let mem_style = if process.memory_mb > 500.0 {
+                theme.danger
+            } else if process.memory_mb > 100.0 {
+                theme.warning
+            } else {
+                theme.text
+            };
+            Cell::from(format_mb(process.memory_mb)).style(mem_style)
+        }
+        Column::Cpu => {
+            let cpu_style = if process.cpu_usage > 80.0 {
+                theme.danger
+            } else if process.cpu_usage > 40.0 {
+                theme.warning
+            } else {
+                theme.text
+            };
+            Cell::from(format!("{:.1}", process.cpu_usage)).style(cpu_style)
+        }
+        Column::Fds => Cell::from(
+            process
+                .fd_count
+                .map_or_else(|| "-".to_string(), |count| count.to_string()),
+        ),
+        Column::Threads => Cell::from(
+            process
+                .threads
+                .map_or_else(|| "-".to_string(), |count| count.to_string()),
+        ),
+        Column::DiskRead => Cell::from(format!(
+            "{}/s",
+            format_bytes(byte_rate(process.disk_read_bytes, disk_interval))
+        )),
+        Column::Uptime => Cell::from(format_uptime(process.uptime())),
+        Column::DiskWrite => Cell::from(format!(
+            "{}/s",
+            format_bytes(byte_rate(process.disk_written_bytes, disk_interval))
+        )),
+    }
 }
 
 /// All the details in a cute little box
-fn render_process_details(f: &mut Frame, app: &App, area: Rect) {
+fn render_process_details(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
@@ -189,39 +939,587 @@ fn render_process_details(f: &mut Frame, app: &App, area: Rect) {
     let inner_area = block.inner(area);
     f.render_widget(block, area);
 
-    if let Some(process) = app.selected_process() {
-        let details = vec![
-            Line::from(vec!["PID: ".into(), process.pid.clone().yellow()]),
-            Line::from(vec!["Name: ".into(), process.name.clone().yellow()]),
-            Line::from(vec![
-                "Memory: ".into(),
-                format!("{:.2} MB", process.memory_mb).yellow(),
-            ]),
-        ];
+    let Some(process) = app.selected_process() else {
+        if app.processes.is_empty() {
+            f.render_widget(Paragraph::new(NO_PROCESSES).style(theme.dim), inner_area);
+        }
+        return;
+    };
+    if app.details_layout == DetailsLayout::Compact {
+        f.render_widget(Paragraph::new(compact_details(app, process)), inner_area);
+        return;
+    }
 
-        let text = Paragraph::new(details).alignment(Alignment::Left);
+    let sections = detail_sections(app, process);
+    let details: Vec<Line> = if app.details_layout == DetailsLayout::Expanded {
+        let mut details = Vec::new();
+        for (n, (title, lines)) in sections.into_iter().enumerate() {
+            if n > 0 {
+                details.push(Line::default());
+            }
+            if let Some(title) = title {
+                details.push(Line::from(title.set_style(theme.header)));
+            }
+            details.extend(lines);
+        }
+        details
+    } else {
+        sections.into_iter().flat_map(|(_, lines)| lines).collect()
+    };
+
+    let details_area = if app.show_threads {
+        let [details_area, threads_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(inner_area);
+        render_thread_list(f, app, threads_area);
+        details_area
+    } else {
+        inner_area
+    };
+    let [text_area, history_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(details_area);
 
-        f.render_widget(text, inner_area);
+    // Roughly how many rows the wrapped text takes, so scrolling stops at its last line
+    let width = text_area.width.max(1) as usize;
+    let rows: usize = details
+        .iter()
+        .map(|line| line.width().div_ceil(width).max(1))
+        .sum();
+    let max_scroll = rows.saturating_sub(text_area.height as usize) as u16;
+    app.details_rows = text_area.height;
+    app.details_scroll = app.details_scroll.min(max_scroll);
+
+    let text = Paragraph::new(details)
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true })
+        .scroll((app.details_scroll, 0));
+    f.render_widget(text, text_area);
+
+    if let Some(process) = app.selected_process() {
+        render_usage_history(f, app, process, history_area);
+    }
+}
+
+/// The details panel's share of the screen in the current layout
+fn details_constraint(app: &App) -> Constraint {
+    if app.details_height == 0 {
+        return Constraint::Length(0);
+    }
+    match app.details_layout {
+        DetailsLayout::Standard => Constraint::Length(app.details_height),
+        // Borders and one line
+        DetailsLayout::Compact => Constraint::Length(3),
+        DetailsLayout::Expanded => Constraint::Percentage(50),
     }
 }
 
-fn render_help_bar(f: &mut Frame, app: &App, area: Rect) {
-    let mut help_text = vec![
-        "↑/↓".fg(Color::Yellow),
-        " Navigate   ".into(),
-        "p/n/m".fg(Color::Yellow),
-        " Sort by PID/Name/Memory   ".into(),
-        "/".fg(Color::Yellow),
-        " Search   ".into(),
-        "k".fg(Color::Yellow),
-        " Kill Process   ".into(),
-        "q".fg(Color::Yellow),
-        " Quit".into(),
+/// The essentials on one line, for the compact layout
+fn compact_details<'a>(app: &App, process: &'a ProcessInfo) -> Line<'a> {
+    let theme = &app.theme;
+    let separator = || " · ".set_style(theme.dim);
+    Line::from(vec![
+        process.pid.to_string().set_style(theme.accent),
+        " ".into(),
+        process.name.as_str().set_style(theme.accent),
+        separator(),
+        process.user.as_deref().unwrap_or("-").into(),
+        separator(),
+        format!("{:.1} % CPU", process.cpu_usage).into(),
+        separator(),
+        format_mb(process.memory_mb).into(),
+        separator(),
+        process.status.to_string().into(),
+        separator(),
+        format!("up {}", format_uptime(process.uptime())).into(),
+    ])
+}
+
+/// Everything the details panel shows, grouped for the expanded layout. The standard layout
+/// runs the same lines together without the titles.
+fn detail_sections(
+    app: &App,
+    process: &ProcessInfo,
+) -> Vec<(Option<&'static str>, Vec<Line<'static>>)> {
+    let theme = app.theme;
+    let optional = |count: Option<String>| {
+        count.map_or_else(
+            || "-".set_style(theme.dim),
+            |count| count.set_style(theme.accent),
+        )
+    };
+
+    let about = vec![
+        Line::from(vec![
+            "PID: ".into(),
+            process.pid.to_string().set_style(theme.accent),
+        ]),
+        Line::from(vec![
+            "Name: ".into(),
+            process.name.clone().set_style(theme.accent),
+        ]),
+        Line::from(vec![
+            "User: ".into(),
+            process
+                .user
+                .clone()
+                .unwrap_or_else(|| "-".to_string())
+                .set_style(theme.accent),
+        ]),
+        Line::from(vec![
+            "Parent: ".into(),
+            optional(process.parent.map(|pid| pid.to_string())),
+        ]),
+    ];
+
+    let resources = vec![
+        Line::from(vec![
+            "CPU: ".into(),
+            format!("{:.1} %", process.cpu_usage).set_style(theme.accent),
+        ]),
+        Line::from(vec![
+            "Memory: ".into(),
+            format_mb(process.memory_mb).set_style(theme.accent),
+        ]),
+        Line::from(vec![
+            "Disk: ".into(),
+            format!(
+                "{}/s read, {}/s written",
+                format_bytes(byte_rate(process.disk_read_bytes, app.disk_interval)),
+                format_bytes(byte_rate(process.disk_written_bytes, app.disk_interval))
+            )
+            .set_style(theme.accent),
+        ]),
+        Line::from(vec![
+            "Threads: ".into(),
+            optional(process.threads.map(|count| count.to_string())),
+            "  Open files: ".into(),
+            optional(process.fd_count.map(|count| count.to_string())),
+        ]),
+        Line::from(vec!["Network (namespace): ".into(), network_rates(app)]),
     ];
 
+    let mut state = vec![
+        Line::from(vec![
+            "State: ".into(),
+            format!("{} ({})", process.status, state_code(process.status)).set_style(theme.accent),
+            app.status_duration(process)
+                .map(|elapsed| format!(" for {}", format_elapsed(elapsed)))
+                .unwrap_or_default()
+                .into(),
+        ]),
+        Line::from(vec![
+            "Started: ".into(),
+            format_timestamp(process.start_time).set_style(theme.accent),
+            format!(" (up {})", format_uptime(process.uptime())).into(),
+        ]),
+    ];
+    if is_blocked_on_io(process.status) {
+        state.push(Line::from(
+            "Blocked on I/O, signals (even SIGKILL) wait until it completes"
+                .set_style(theme.danger),
+        ));
+    }
+
+    let command = vec![
+        Line::from(vec![
+            "Executable: ".into(),
+            match &process.exe_path {
+                Some(path) => path.display().to_string().set_style(theme.accent),
+                None => "[unknown]".set_style(theme.dim),
+            },
+        ]),
+        Line::from(vec![
+            "Command: ".into(),
+            if process.cmd.is_empty() {
+                "[no command line]".set_style(theme.dim)
+            } else {
+                process.cmd.join(" ").set_style(theme.accent)
+            },
+        ]),
+    ];
+
+    let mut sections = vec![
+        (Some("Process"), about),
+        (Some("Resources"), resources),
+        (Some("State"), state),
+        (Some("Command"), command),
+    ];
+    if app.show_environment {
+        // Titled in every layout, the variables would run into the command line otherwise
+        let environment = match &app.environment {
+            Some(variables) => {
+                std::iter::once(Line::from(format!("Environment ({}):", variables.len())))
+                    .chain(variables.iter().map(|(name, value)| {
+                        Line::from(vec![
+                            name.clone().set_style(theme.accent),
+                            format!("={value}").into(),
+                        ])
+                    }))
+                    .collect()
+            }
+            None => vec![Line::from(
+                "Environment not accessible".set_style(theme.dim),
+            )],
+        };
+        sections.push((None, environment));
+    }
+
+    sections
+}
+
+/// CPU and memory sparklines of the selected process, newest sample on the right
+fn render_usage_history(f: &mut Frame, app: &App, process: &ProcessInfo, area: Rect) {
+    let Some(history) = app.usage_history.get(&process.pid) else {
+        return;
+    };
+    let [cpu_area, memory_area] =
+        Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+
+    // Only the latest samples that fit, so the newest one is always visible
+    let fit = |samples: &VecDeque<u64>, width: u16| {
+        samples
+            .iter()
+            .skip(samples.len().saturating_sub(width as usize))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    let cpu = Sparkline::default()
+        .block(Block::default().title(format!("CPU {:.1} %", process.cpu_usage)))
+        .data(fit(&history.cpu, cpu_area.width))
+        .style(app.theme.success);
+    let memory = Sparkline::default()
+        .block(Block::default().title(format!("Memory {}", format_mb(process.memory_mb))))
+        .data(fit(&history.memory_mb, memory_area.width))
+        .style(app.theme.accent);
+
+    f.render_widget(cpu, cpu_area);
+    f.render_widget(memory, memory_area);
+}
+
+/// Network rates of the whole namespace the process is in, the closest Linux gets to
+/// per-process traffic. The label says so, most processes share the host's namespace.
+fn network_rates(app: &App) -> Span<'static> {
+    if !NET_SUPPORTED {
+        return "n/a".set_style(app.theme.dim);
+    }
+    match app.net_rates {
+        Some((received, transmitted)) => format!(
+            "↓ {}/s ↑ {}/s",
+            format_bytes(received),
+            format_bytes(transmitted)
+        )
+        .set_style(app.theme.accent),
+        None => "measuring...".set_style(app.theme.dim),
+    }
+}
+
+/// The hottest threads of the selected process first
+fn render_thread_list(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+
+    let Some(threads) = &app.threads else {
+        let text = Paragraph::new("Threads not accessible").style(theme.dim);
+        f.render_widget(text, area);
+        return;
+    };
+
+    let header = Row::new(["TID", "Thread", "State", "CPU %"]).style(theme.header);
+    let rows = threads.iter().map(|thread| {
+        Row::new([
+            Cell::from(thread.tid.to_string()),
+            Cell::from(thread.name.clone()),
+            Cell::from(thread.state.to_string()),
+            Cell::from(format!("{:.1}", thread.cpu_usage)),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Min(10),
+            Constraint::Length(5),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::LEFT)
+            .title(format!(" Threads ({}) ", threads.len())),
+    );
+
+    f.render_widget(table, area);
+}
+
+/// Which keys a binding lists: actions looked up in the keymap, or keys it doesn't cover
+enum Keys {
+    /// Groups of related actions. Within a group their keys are joined by `/`, first keys
+    /// first, so `[[Previous, Next]]` shows as `↑/↓ k/j`.
+    Actions(&'static [&'static [Action]]),
+    /// Keys the keymap doesn't handle, like Tab while searching
+    Fixed(&'static str),
+}
+
+impl Keys {
+    /// The keys as the loaded keymap has them
+    fn label(&self, keymap: &Keymap) -> String {
+        let groups = match self {
+            Keys::Fixed(keys) => return keys.to_string(),
+            Keys::Actions(groups) => groups,
+        };
+
+        let mut parts = Vec::new();
+        for group in *groups {
+            let keys: Vec<Vec<String>> = group.iter().map(|&action| keymap.keys(action)).collect();
+            let alternatives = keys.iter().map(Vec::len).max().unwrap_or(0);
+            for n in 0..alternatives {
+                let nth: Vec<&str> = keys
+                    .iter()
+                    .filter_map(|k| k.get(n))
+                    .map(String::as_str)
+                    .collect();
+                parts.push(nth.join("/"));
+            }
+        }
+        if parts.is_empty() {
+            "unbound".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+}
+
+// A keybinding as (keys, what they do). The help bar and the `?` overlay both draw from
+// these, so the two can't drift apart.
+type Binding = (Keys, &'static str);
+
+const NAVIGATE: Binding = (
+    Keys::Actions(&[
+        &[Action::Previous, Action::Next],
+        &[Action::First, Action::Last],
+    ]),
+    "Navigate",
+);
+const HALF_PAGE: Binding = (
+    Keys::Actions(&[&[Action::HalfPageDown, Action::HalfPageUp]]),
+    "Half a screen down/up",
+);
+const PAGE: Binding = (
+    Keys::Actions(&[&[Action::PreviousPage, Action::NextPage]]),
+    "Page",
+);
+const SCROLL_DETAILS: Binding = (
+    Keys::Actions(&[&[Action::ScrollDetailsUp, Action::ScrollDetailsDown]]),
+    "Scroll the details panel",
+);
+const ENVIRONMENT: Binding = (
+    Keys::Actions(&[&[Action::Environment]]),
+    "Environment variables (in the details)",
+);
+const SORT: Binding = (
+    Keys::Actions(&[&[
+        Action::SortPid,
+        Action::SortName,
+        Action::SortCpu,
+        Action::SortMemory,
+    ]]),
+    "Sort by PID/Name/CPU/Memory",
+);
+const FDS: Binding = (
+    Keys::Actions(&[&[Action::SortFds, Action::FdColumn]]),
+    "Sort/Toggle FDs",
+);
+const DISK_IO: Binding = (
+    Keys::Actions(&[&[Action::SortDiskIo, Action::DiskColumns]]),
+    "Sort/Toggle Disk I/O",
+);
+const THREADS: Binding = (
+    Keys::Actions(&[&[
+        Action::SortThreads,
+        Action::ThreadsColumn,
+        Action::ThreadList,
+    ]]),
+    "Sort/Toggle/List Threads",
+);
+const UPTIME: Binding = (Keys::Actions(&[&[Action::SortUptime]]), "Sort by Uptime");
+const SEARCH: Binding = (Keys::Actions(&[&[Action::Search]]), "Search");
+const FUZZY: Binding = (
+    Keys::Fixed("Tab"),
+    "Switch between exact and fuzzy (while searching)",
+);
+const STATUS_FILTER: Binding = (Keys::Actions(&[&[Action::StatusFilter]]), "Status Filter");
+const MEMORY_FILTER: Binding = (Keys::Actions(&[&[Action::MemoryFilter]]), "Min Memory");
+const KERNEL_THREADS: Binding = (
+    Keys::Actions(&[&[Action::KernelThreads]]),
+    "Hide/Show kernel threads",
+);
+const MY_PROCESSES: Binding = (
+    Keys::Actions(&[&[Action::MyProcesses]]),
+    "Only my processes (:user <name> for anyone's)",
+);
+const TREE: Binding = (Keys::Actions(&[&[Action::Tree]]), "Tree");
+const FOLD: Binding = (
+    Keys::Actions(&[&[Action::Fold]]),
+    "Fold/Unfold (in the tree)",
+);
+const LAYOUT: Binding = (Keys::Actions(&[&[Action::Layout]]), "Layout");
+const COLUMNS: Binding = (Keys::Actions(&[&[Action::Columns]]), "Columns");
+const THEME: Binding = (Keys::Actions(&[&[Action::Theme]]), "Cycle Theme");
+const PER_CORE: Binding = (Keys::Actions(&[&[Action::PerCoreCpu]]), "Per-core CPU");
+const DETAILS_LAYOUT: Binding = (
+    Keys::Actions(&[&[Action::DetailsLayout]]),
+    "Details layout (standard/compact/expanded)",
+);
+const KILL: Binding = (Keys::Actions(&[&[Action::Kill]]), "Kill Process");
+const MARK: Binding = (Keys::Actions(&[&[Action::Mark]]), "Mark for a bulk kill");
+const PIN: Binding = (Keys::Actions(&[&[Action::Pin]]), "Pin to the top");
+#[cfg(unix)]
+const FREEZE: Binding = (Keys::Actions(&[&[Action::Freeze]]), "Freeze/Thaw");
+#[cfg(unix)]
+const RENICE: Binding = (Keys::Actions(&[&[Action::Renice]]), "Renice");
+const COMMAND: Binding = (
+    Keys::Actions(&[&[Action::Command]]),
+    "Command (kill <name>, user <name>, <PID> to jump)",
+);
+const RUN_COMMAND: Binding = (Keys::Actions(&[&[Action::RunCommand]]), "Run Command");
+const LOG: Binding = (
+    Keys::Actions(&[&[Action::Log]]),
+    "Log of messages and actions",
+);
+const EXPORT: Binding = (
+    Keys::Actions(&[&[Action::ExportJson, Action::ExportCsv]]),
+    "Export JSON/CSV",
+);
+const OPEN_FOLDER: Binding = (
+    Keys::Actions(&[&[Action::OpenFolder]]),
+    "Open the executable's folder",
+);
+const SNAPSHOT_GONE: Binding = (
+    Keys::Actions(&[&[Action::SnapshotGone]]),
+    "Gone since snapshot",
+);
+const REFRESH: Binding = (
+    Keys::Actions(&[&[Action::SlowerRefresh, Action::FasterRefresh]]),
+    "Refresh",
+);
+const SET_INTERVAL: Binding = (
+    Keys::Actions(&[&[Action::SetInterval]]),
+    "Type the refresh interval",
+);
+const HELP: Binding = (Keys::Actions(&[&[Action::Help]]), "Help");
+const QUIT: Binding = (Keys::Actions(&[&[Action::Quit]]), "Quit");
+
+/// Everything the `?` overlay lists, by category
+const KEYBINDINGS: &[(&str, &[Binding])] = &[
+    (
+        "Navigation",
+        &[
+            NAVIGATE,
+            HALF_PAGE,
+            PAGE,
+            SCROLL_DETAILS,
+            ENVIRONMENT,
+            TREE,
+            FOLD,
+            LAYOUT,
+            COLUMNS,
+            THEME,
+            PER_CORE,
+            DETAILS_LAYOUT,
+        ],
+    ),
+    ("Sorting", &[SORT, FDS, DISK_IO, THREADS, UPTIME]),
+    (
+        "Search",
+        &[
+            SEARCH,
+            FUZZY,
+            STATUS_FILTER,
+            MEMORY_FILTER,
+            KERNEL_THREADS,
+            MY_PROCESSES,
+        ],
+    ),
+    (
+        "Actions",
+        &[
+            KILL,
+            MARK,
+            PIN,
+            #[cfg(unix)]
+            FREEZE,
+            #[cfg(unix)]
+            RENICE,
+            COMMAND,
+            RUN_COMMAND,
+            LOG,
+            EXPORT,
+            OPEN_FOLDER,
+            SNAPSHOT_GONE,
+            REFRESH,
+            SET_INTERVAL,
+            HELP,
+            QUIT,
+        ],
+    ),
+];
+
+fn render_help_bar(f: &mut Frame, app: &App, keymap: &Keymap, area: Rect) {
+    let theme = &app.theme;
+    let mut help_text = Vec::new();
+    let mut add = |(keys, action): (Keys, &str)| {
+        help_text.push(keys.label(keymap).set_style(theme.accent));
+        help_text.push(format!(" {action}   ").into());
+    };
+
+    add(NAVIGATE);
+    add(SORT);
+    if FD_COUNT_SUPPORTED {
+        add(FDS);
+    }
+    add(SEARCH);
+    add(STATUS_FILTER);
+    add((
+        MEMORY_FILTER.0,
+        &format!(
+            "{} ({})",
+            MEMORY_FILTER.1,
+            app.min_memory_mb
+                .map_or_else(|| "off".to_string(), format_mb)
+        ),
+    ));
+    add(KILL);
+    add((
+        REFRESH.0,
+        &format!("{} ({}s)", REFRESH.1, app.refresh_interval.as_secs_f32()),
+    ));
+    if app.page_size > 0 {
+        add(PAGE);
+    }
+    add(DISK_IO);
+    if THREADS_SUPPORTED {
+        add(THREADS);
+    }
+    add(if app.tree_view {
+        (TREE.0, "Flat/Enter Fold")
+    } else {
+        TREE
+    });
+    if app.presets.len() > 1 {
+        add(LAYOUT);
+    }
+    if app.custom_command.is_some() {
+        add(RUN_COMMAND);
+    }
+    add(HELP);
+    add(QUIT);
+
     if !app.search_query.is_empty() {
         help_text.push("   Filter: ".into());
-        help_text.push(app.search_query.clone().blue());
+        help_text.push(app.search_query.clone().set_style(theme.filter));
     }
 
     let help = Paragraph::new(Line::from(help_text))
@@ -230,25 +1528,153 @@ fn render_help_bar(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(help, area);
 }
 
+/// A checklist of every column, space toggles the highlighted one
+fn render_column_menu(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let mut lines: Vec<Line> = Column::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, &column)| {
+            let (before, key, rest) = column.header();
+            let check = if app.shows(column) { "[x]" } else { "[ ]" };
+            let text = format!("{check} {before}{key}{rest}");
+            if !column.is_supported() {
+                Line::from(format!("  {text} (not available)")).style(theme.dim)
+            } else if i == app.column_menu_index {
+                Line::from(format!("> {text}")).style(theme.highlight)
+            } else {
+                Line::from(format!("  {text}"))
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        "Space".set_style(theme.accent),
+        " Toggle   ".into(),
+        "Esc".set_style(theme.accent),
+        " Close".into(),
+    ]));
+
+    let area = centered_rect(40, lines.len() as u16 + 2, f.area());
+    let popup_block = Block::default()
+        .title("Columns")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(theme.popup);
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).style(theme.text).block(popup_block),
+        area,
+    );
+}
+
+/// Every keybinding, grouped the way `KEYBINDINGS` lists them
+fn render_help_overlay(f: &mut Frame, app: &App, keymap: &Keymap) {
+    let theme = &app.theme;
+    let mut rows = Vec::new();
+    for (n, (category, bindings)) in KEYBINDINGS.iter().enumerate() {
+        let category_row = Row::new([Cell::from(category.set_style(theme.header))]);
+        rows.push(if n > 0 {
+            category_row.top_margin(1)
+        } else {
+            category_row
+        });
+        rows.extend(bindings.iter().map(|(keys, action)| {
+            Row::new([
+                Cell::from(keys.label(keymap).set_style(theme.accent)),
+                Cell::from(*action).style(theme.text),
+            ])
+        }));
+    }
+
+    // One row per binding and category, plus the gaps between categories and the borders
+    let height = rows.len() + KEYBINDINGS.len() - 1 + 2;
+    let area = centered_rect(60, height as u16, f.area());
+    let popup_block = Block::default()
+        .title("Keybindings (any key to close)")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(theme.popup);
+
+    f.render_widget(Clear, area);
+
+    let table = Table::new(rows, [Constraint::Length(15), Constraint::Fill(1)]).block(popup_block);
+    f.render_widget(table, area);
+}
+
+/// The newest entries at the bottom, scrolled back by `log_scroll`
+fn render_log(f: &mut Frame, app: &mut App) {
+    let theme = app.theme;
+    let area = centered_rect_pct(80, 60, f.area());
+    let block = Block::default()
+        .title(format!(
+            "Log ({} entries, ↑/↓ PgUp/PgDn scroll, l closes)",
+            app.log.len()
+        ))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(theme.popup);
+    let inner = block.inner(area);
+    app.log_rows = inner.height.max(1) as usize;
+
+    let end = app.log.len() - app.log_scroll.min(app.log.len());
+    let start = end.saturating_sub(app.log_rows);
+    let lines: Vec<Line> = app
+        .log
+        .range(start..end)
+        .map(|entry| {
+            Line::from(vec![
+                format!("{}  ", format_timestamp(entry.at)).set_style(theme.dim),
+                entry.text.clone().set_style(theme.message(entry.severity)),
+            ])
+        })
+        .collect();
+
+    f.render_widget(Clear, area);
+    let text = if lines.is_empty() {
+        Paragraph::new("Nothing happened yet".set_style(theme.dim))
+    } else {
+        Paragraph::new(lines)
+    };
+    f.render_widget(text.block(block), area);
+}
+
 fn render_search_popup(f: &mut Frame, app: &App) {
     let area = centered_rect(50, 3, f.area());
     let popup_block = Block::default()
-        .title("Search")
+        .title(if app.fuzzy_search {
+            "Search (fuzzy, Tab for exact)"
+        } else {
+            "Search (Tab for fuzzy)"
+        })
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .style(Style::default().bg(Color::DarkGray));
+        .border_style(if app.search_regex.invalid {
+            app.theme.danger
+        } else {
+            Style::default()
+        })
+        .style(app.theme.popup);
 
     f.render_widget(Clear, area); // Clear the area
     f.render_widget(popup_block, area);
 
-    let text = Paragraph::new(format!("> {}", app.search_query))
-        .style(Style::default().fg(Color::White))
-        .block(Block::default().borders(Borders::NONE));
+    let matches = match app.filtered_processes.len() {
+        1 => "  (1 match)".to_string(),
+        n => format!("  ({n} matches)"),
+    };
+    let text = Paragraph::new(Line::from(vec![
+        format!("> {}", app.search_query).into(),
+        matches.set_style(app.theme.dim),
+    ]))
+    .style(app.theme.text)
+    .block(Block::default().borders(Borders::NONE));
 
     let inner_area = Rect {
         x: area.x + 1,
         y: area.y + 1,
-        width: area.width - 2,
+        width: area.width.saturating_sub(2),
         height: 1,
     };
 
@@ -259,44 +1685,313 @@ fn render_search_popup(f: &mut Frame, app: &App) {
         inner_area.y,
     ));
 }
+
+/// Same look as the search popup, with the reason for a refused value after the input
+fn render_interval_popup(f: &mut Frame, app: &App) {
+    let area = centered_rect(50, 3, f.area());
+    let popup_block = Block::default()
+        .title("Refresh interval in ms (Enter to apply, Esc to cancel)")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(if app.interval_error.is_some() {
+            app.theme.danger
+        } else {
+            Style::default()
+        })
+        .style(app.theme.popup);
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup_block, area);
+
+    let hint = match &app.interval_error {
+        Some(error) => format!("  ({error})").set_style(app.theme.danger),
+        None => format!("  (now {} ms)", app.refresh_interval.as_millis()).set_style(app.theme.dim),
+    };
+    let text = Paragraph::new(Line::from(vec![
+        format!("> {}", app.interval_input).into(),
+        hint,
+    ]))
+    .style(app.theme.text);
+
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+
+    f.render_widget(text, inner_area);
+
+    f.set_cursor_position((
+        inner_area.x + app.interval_input.len() as u16 + 2,
+        inner_area.y,
+    ));
+}
+
+/// Names listed in a bulk kill confirmation before the rest are summed up
+const MAX_LISTED_KILLS: usize = 5;
+
+fn render_command_popup(f: &mut Frame, app: &App) {
+    let area = centered_rect(50, 3, f.area());
+    let popup_block = Block::default()
+        .title("Command (kill <name> [--force], user [<name>], <PID>)")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(app.theme.popup);
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup_block, area);
+
+    let text = Paragraph::new(format!(":{}", app.command_input)).style(app.theme.text);
+
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+
+    f.render_widget(text, inner_area);
+
+    f.set_cursor_position((
+        inner_area.x + app.command_input.len() as u16 + 1,
+        inner_area.y,
+    ));
+}
+
+/// Same look as the search popup, with the nice value being typed
+#[cfg(unix)]
+fn render_renice_popup(f: &mut Frame, app: &App) {
+    let Some(target) = app.renice_target.as_ref() else {
+        return;
+    };
+
+    let area = centered_rect(50, 3, f.area());
+    let popup_block = Block::default()
+        .title(format!("Nice for {} (-20 to 19)", target.name))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(app.theme.popup);
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup_block, area);
+
+    let text = Paragraph::new(Line::from(vec![
+        format!("> {}", target.input).into(),
+        "  (Enter to apply, Esc to cancel)".set_style(app.theme.dim),
+    ]))
+    .style(app.theme.text);
+
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+
+    f.render_widget(text, inner_area);
+
+    f.set_cursor_position((inner_area.x + target.input.len() as u16 + 2, inner_area.y));
+}
+
 fn render_kill_confirmation(f: &mut Frame, app: &App) {
+    let Some(target) = app.kill_target.as_ref() else {
+        return;
+    };
+
+    let mut lines = match target.processes.as_slice() {
+        [(pid, name)] if target.escalation => vec![
+            Line::from(format!("{name} ({pid}) is still running after SIGTERM."))
+                .style(app.theme.danger),
+            Line::from("Force it to exit?"),
+        ],
+        [(pid, name)] => vec![
+            Line::from(format!(
+                "Are you sure you want to kill process: {} ({})?",
+                name, pid
+            ))
+            .style(app.theme.danger),
+        ],
+        processes => {
+            let question = if target.escalation {
+                format!(
+                    "{} processes are still running after SIGTERM, force them to exit?",
+                    processes.len()
+                )
+            } else {
+                format!(
+                    "Are you sure you want to kill {} processes?",
+                    processes.len()
+                )
+            };
+            let mut lines = vec![Line::from(question).style(app.theme.danger)];
+            lines.extend(
+                processes
+                    .iter()
+                    .take(MAX_LISTED_KILLS)
+                    .map(|(pid, name)| Line::from(format!("{name} ({pid})"))),
+            );
+            if processes.len() > MAX_LISTED_KILLS {
+                lines.push(Line::from(format!(
+                    "...and {} more",
+                    processes.len() - MAX_LISTED_KILLS
+                )));
+            }
+            lines
+        }
+    };
+    lines.push(Line::from(""));
+
+    let height = lines.len() + KillSignal::ALL.len() + 4;
+    let area = centered_rect(60, height as u16, f.area());
+    let title = match app.kill_time_left() {
+        // Rounded up, so the countdown ends on 1s rather than sitting at 0s
+        Some(left) => format!(
+            "Confirm Kill Process (auto-cancel in {}s)",
+            left.as_secs() + u64::from(left.subsec_nanos() > 0)
+        ),
+        None => "Confirm Kill Process".to_string(),
+    };
+    let popup_block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(app.theme.popup);
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup_block, area);
+
+    for signal in KillSignal::ALL {
+        let text = format!("{:<8} {:<16}", signal.name(), signal.description());
+        lines.push(if !signal.is_supported() {
+            Line::from(format!("  {text}  ")).style(app.theme.dim)
+        } else if signal == target.signal {
+            Line::from(format!("> {text}  ")).style(app.theme.highlight)
+        } else {
+            Line::from(format!("  {text}  "))
+        });
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        "↑/↓ to pick a signal, (Y) or Enter to confirm, any other key to cancel.",
+    ));
+
+    let text = Paragraph::new(lines).alignment(Alignment::Center);
+
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+
+    f.render_widget(text, inner_area);
+}
+
+fn render_quit_confirmation(f: &mut Frame, app: &App) {
     let area = centered_rect(60, 5, f.area());
     let popup_block = Block::default()
-        .title("Confirm Kill Process")
+        .title("Confirm Quit")
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .style(Style::default().bg(Color::DarkGray));
+        .style(app.theme.popup);
 
     f.render_widget(Clear, area);
     f.render_widget(popup_block, area);
 
-    let process_name = app
-        .selected_process()
-        .map(|p| &p.name as &str)
-        .unwrap_or("");
+    let question = match app.busy_activity() {
+        Some(reason) => format!("Really quit? {reason}."),
+        None => "Quit? (y/n)".to_string(),
+    };
 
     let text = Paragraph::new(vec![
-        Line::from(format!(
-            "Are you sure you want to kill process: {}?",
-            process_name
-        ))
-        .style(Style::default().fg(Color::Red)),
+        Line::from(question).style(app.theme.warning),
         Line::from(""),
-        Line::from("Press (Y) to confirm, any other key to cancel."),
+        Line::from("Press (Y) to quit, any other key to cancel."),
     ])
     .alignment(Alignment::Center);
 
     let inner_area = Rect {
         x: area.x + 1,
         y: area.y + 1,
-        width: area.width - 2,
-        height: area.height - 2,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
     };
 
     f.render_widget(text, inner_area);
 }
 
-fn render_message(f: &mut Frame, message: &str, color: Color) {
+fn render_jump_confirmation(f: &mut Frame, app: &App) {
+    let Some(pid) = app.jump_target else {
+        return;
+    };
+
+    let area = centered_rect(60, 5, f.area());
+    let popup_block = Block::default()
+        .title("Jump to PID")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(app.theme.popup);
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup_block, area);
+
+    let text = Paragraph::new(vec![
+        Line::from(format!("PID {pid} is hidden by the current filters.")).style(app.theme.warning),
+        Line::from(""),
+        Line::from("Press (Y) to clear them and jump, any other key to cancel."),
+    ])
+    .alignment(Alignment::Center);
+
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+
+    f.render_widget(text, inner_area);
+}
+
+/// Processes from the snapshot that have since exited
+fn render_snapshot_gone(f: &mut Frame, app: &App) {
+    let Some(snapshot) = &app.snapshot else {
+        return;
+    };
+
+    let area = centered_rect_pct(60, 50, f.area());
+    let popup_block = Block::default()
+        .title(format!("Gone since {}", snapshot.path.display()))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(app.theme.popup);
+
+    f.render_widget(Clear, area);
+
+    let rows = app.snapshot_diff.gone.iter().map(|&i| {
+        let entry = &snapshot.entries[i];
+        Row::new([
+            Cell::from(entry.pid.to_string()),
+            Cell::from(entry.name.clone()),
+            Cell::from(entry.memory_mb.map_or_else(|| "-".to_string(), format_mb)),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(15),
+            Constraint::Fill(55),
+            Constraint::Fill(30),
+        ],
+    )
+    .header(Row::new(["PID", "Name", "Memory"]).style(app.theme.header))
+    .block(popup_block);
+
+    f.render_widget(table, area);
+}
+
+fn render_message(f: &mut Frame, message: &str, style: Style) {
     let area = centered_rect(50, 3, f.area());
 
     f.render_widget(Clear, area);
@@ -305,8 +2000,8 @@ fn render_message(f: &mut Frame, message: &str, color: Color) {
     let inner_area = Rect {
         x: area.x + 1,
         y: area.y + 1,
-        width: area.width - 2,
-        height: area.height - 2,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
     };
 
     let popup_block = Block::default()
@@ -317,8 +2012,61 @@ fn render_message(f: &mut Frame, message: &str, color: Color) {
     f.render_widget(popup_block, area);
 
     let text = Paragraph::new(message)
-        .style(Style::default().fg(color))
+        .style(style)
         .alignment(Alignment::Center);
 
     f.render_widget(text, inner_area);
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        backend::TestBackend,
+        style::{Color, Modifier},
+    };
+
+    use super::*;
+    use crate::config::{Config, Preferences};
+
+    #[test]
+    fn plain_theme_draws_without_styles() {
+        let mut app = App::new(&Config::default(), Preferences::default());
+        app.processes = vec![
+            ProcessInfo::sample(1, "init", 12.0),
+            ProcessInfo::sample(42, "bash", 3.5),
+        ];
+        app.theme = Theme::plain();
+        app.search_query = "ba".to_string();
+        app.apply_filters();
+        app.table_state.select(Some(0));
+        app.set_message("Sent SIGTERM to bash".to_string(), Severity::Success);
+        let keymap = Keymap::default();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        for mode in [
+            InputMode::Normal,
+            InputMode::Search,
+            InputMode::ConfirmKill,
+            InputMode::ConfirmQuit,
+            InputMode::Command,
+            InputMode::Help,
+            InputMode::ColumnMenu,
+            InputMode::Log,
+            InputMode::SetInterval,
+        ] {
+            if mode == InputMode::ConfirmKill {
+                app.begin_kill();
+            }
+            app.input_mode = mode;
+            terminal.draw(|f| ui(f, &mut app, &keymap)).unwrap();
+
+            let buffer = terminal.backend().buffer();
+            for (i, cell) in buffer.content().iter().enumerate() {
+                let (x, y) = buffer.pos_of(i);
+                assert_eq!(cell.fg, Color::Reset, "foreground at {x},{y}");
+                assert_eq!(cell.bg, Color::Reset, "background at {x},{y}");
+                assert_eq!(cell.modifier, Modifier::empty(), "modifier at {x},{y}");
+            }
+        }
+    }
+}
@@ -1,16 +1,235 @@
+use std::time::Duration;
+
 use ratatui::layout::Rect;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Simple util to create a new rectangle which in centered inside another rectangle
-/// with a percentage original width and given height
+/// with a percentage original width and given height, clamped to fit inside `r`
 pub fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
-    let width = (r.width * percent_x) / 100;
-    let x = r.x + (r.width - width) / 2;
-    let y = r.y + (r.height - height) / 2;
+    let width = percent_of(r.width, percent_x);
+    let height = height.min(r.height);
+    centered(width, height, r)
+}
+
+/// Like `centered_rect`, but the height is a percentage of `r` too
+pub fn centered_rect_pct(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let width = percent_of(r.width, percent_x);
+    let height = percent_of(r.height, percent_y);
+    centered(width, height, r)
+}
 
+/// `percent` of `length`, never more than `length` itself
+fn percent_of(length: u16, percent: u16) -> u16 {
+    (u32::from(length) * u32::from(percent.min(100)) / 100) as u16
+}
+
+fn centered(width: u16, height: u16, r: Rect) -> Rect {
     Rect {
-        x,
-        y,
+        x: r.x + r.width.saturating_sub(width) / 2,
+        y: r.y + r.height.saturating_sub(height) / 2,
         width,
         height,
     }
 }
+
+/// Compact elapsed time like `45s`, `3m`, `2h 5m` or `4d 1h`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Process uptime as `HH:MM:SS`, with a `Dd ` prefix once it passes a day
+pub fn format_uptime(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    );
+    match secs / 86400 {
+        0 => clock,
+        days => format!("{days}d {clock}"),
+    }
+}
+
+/// Seconds since the epoch as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_timestamp(epoch_secs: u64) -> String {
+    // Civil date from a day count, after Howard Hinnant's `civil_from_days`
+    let days = (epoch_secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let secs = epoch_secs % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Sizes like `512 B`, `1.50 KB`, `23.4 MB` or `8.00 GB`, with two decimals below 10 and one above
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if value < 10.0 {
+        format!("{value:.2} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// `format_bytes` for the MB values sysinfo data is kept in
+pub fn format_mb(mb: f64) -> String {
+    format_bytes((mb * 1024.0 * 1024.0) as u64)
+}
+
+/// `s` cut to at most `max` terminal columns, with a `…` at the end if it didn't fit.
+/// Wide characters like CJK and most emoji take two columns.
+pub fn truncate_end(s: &str, max: usize) -> String {
+    if s.width() <= max {
+        return s.to_string();
+    }
+    let Some(room) = max.checked_sub(1) else {
+        return String::new();
+    };
+    let mut out = take_columns(s.chars(), room);
+    out.push('…');
+    out
+}
+
+/// `s` cut to at most `max` terminal columns, with a `…` in place of the middle if it didn't
+/// fit. Keeps both ends, which tell long paths and versioned names apart best.
+pub fn truncate_middle(s: &str, max: usize) -> String {
+    if s.width() <= max {
+        return s.to_string();
+    }
+    let Some(room) = max.checked_sub(1) else {
+        return String::new();
+    };
+    let tail = room / 2;
+    let mut out = take_columns(s.chars(), room - tail);
+    out.push('…');
+    let end: String = take_columns(s.chars().rev(), tail).chars().rev().collect();
+    out.push_str(&end);
+    out
+}
+
+/// As many of `chars` as fit in `columns`, stopping at the first one that doesn't
+fn take_columns(chars: impl Iterator<Item = char>, columns: usize) -> String {
+    let mut used = 0;
+    chars
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= columns
+        })
+        .collect()
+}
+
+/// Wrap a value in single quotes so `sh` treats it as one literal word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// A CSV field, quoted (with quotes doubled) only when it holds a comma, quote or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popups_fit_a_tiny_terminal() {
+        let area = Rect::new(0, 0, 10, 3);
+        for popup in [centered_rect_pct(90, 90, area), centered_rect(90, 20, area)] {
+            assert!(popup.width <= area.width && popup.height <= area.height);
+            assert_eq!(area.intersection(popup), popup);
+        }
+        assert_eq!(centered_rect_pct(90, 90, area), Rect::new(0, 0, 9, 2));
+    }
+
+    #[test]
+    fn byte_sizes_scale_at_the_boundaries() {
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.00 KB");
+        assert_eq!(format_bytes(1024 * 1024), "1.00 MB");
+        assert_eq!(format_bytes(1536 * 1024 * 1024), "1.50 GB");
+        assert_eq!(format_mb(23.4), "23.4 MB");
+    }
+
+    #[test]
+    fn uptimes_and_elapsed_times() {
+        let secs = Duration::from_secs;
+        assert_eq!(format_uptime(secs(42)), "00:00:42");
+        assert_eq!(format_uptime(secs(5 * 3600 + 7 * 60 + 9)), "05:07:09");
+        assert_eq!(format_uptime(secs(3 * 86400 + 3600 + 1)), "3d 01:00:01");
+        assert_eq!(format_elapsed(secs(42)), "42s");
+        assert_eq!(format_elapsed(secs(5 * 3600 + 7 * 60 + 9)), "5h 7m");
+        assert_eq!(format_elapsed(secs(3 * 86400 + 3600 + 1)), "3d 1h");
+    }
+
+    #[test]
+    fn durations_roll_over_at_minutes_and_hours() {
+        let cases = [
+            (0, "00:00:00", "0s"),
+            (59, "00:00:59", "59s"),
+            (60, "00:01:00", "1m"),
+            (3599, "00:59:59", "59m"),
+            (3600, "01:00:00", "1h 0m"),
+            (10 * 3600 + 30 * 60 + 5, "10:30:05", "10h 30m"),
+        ];
+        for (secs, uptime, elapsed) in cases {
+            assert_eq!(format_uptime(Duration::from_secs(secs)), uptime);
+            assert_eq!(format_elapsed(Duration::from_secs(secs)), elapsed);
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_within_the_width() {
+        assert_eq!(truncate_end("firefox", 7), "firefox");
+        assert_eq!(truncate_end("firefox", 5), "fire…");
+        assert_eq!(truncate_middle("firefox", 7), "firefox");
+        assert_eq!(truncate_middle("firefox-bin", 7), "fir…bin");
+        assert_eq!(truncate_middle("/usr/lib/ld-linux.so", 11), "/usr/…ux.so");
+        assert_eq!(truncate_middle("abc", 1), "…");
+        assert_eq!(truncate_middle("abc", 0), "");
+
+        // Two columns per character, a half that is left over stays empty rather than overflow
+        assert_eq!(truncate_end("日本語のプロセス", 7), "日本語…");
+        assert_eq!(truncate_middle("日本語のプロセス", 7), "日…ス");
+        assert!(truncate_middle("🦀🦀🦀🦀🦀", 6).width() <= 6);
+    }
+}